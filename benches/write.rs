@@ -0,0 +1,108 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use fast_stream::bytes::{Bytes, ValueWrite};
+use fast_stream::stream::Stream;
+use std::hint::black_box;
+use std::io;
+
+/*
+旧写法:每个值先写入新分配的 Stream,再 append 到目标
+ */
+struct Legacy<T>(T);
+impl<T: ValueWrite> ValueWrite for Legacy<T> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        let mut data = self.0.write(&stream.endian)?;
+        data.seek_start()?;
+        stream.append(&mut data)?;
+        Ok(())
+    }
+}
+
+fn primitives(c: &mut Criterion) {
+    let mut group = c.benchmark_group("u32 x 100000");
+    group.bench_function("write", |b| {
+        b.iter(|| {
+            let mut stream = Stream::empty();
+            for value in 0..100_000_u32 {
+                stream.write_value(Legacy(black_box(value))).unwrap();
+            }
+            stream
+        })
+    });
+    group.bench_function("write_to", |b| {
+        b.iter(|| {
+            let mut stream = Stream::empty();
+            for value in 0..100_000_u32 {
+                stream.write_value(black_box(value)).unwrap();
+            }
+            stream
+        })
+    });
+    group.finish();
+}
+
+fn bytes(c: &mut Criterion) {
+    let data = vec![7_u8; 1024 * 1024];
+    let mut group = c.benchmark_group("Vec<u8> 1M");
+    group.bench_function("write", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| {
+                let mut stream = Stream::empty();
+                stream.write_value(Legacy(data)).unwrap();
+                stream
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("write_to", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| {
+                let mut stream = Stream::empty();
+                stream.write_value(data).unwrap();
+                stream
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn nested(c: &mut Criterion) {
+    let data: Vec<Option<Vec<u16>>> = (0..10_000)
+        .map(|i| (i % 3 != 0).then(|| vec![i as u16; 8]))
+        .collect();
+    let legacy = || {
+        data.clone()
+            .into_iter()
+            .map(|value| Legacy(value.map(|values| values.into_iter().map(Legacy).collect())))
+            .collect::<Vec<Legacy<Option<Vec<Legacy<u16>>>>>>()
+    };
+    let mut group = c.benchmark_group("Vec<Option<Vec<u16>>> x 10000");
+    group.bench_function("write", |b| {
+        b.iter_batched(
+            legacy,
+            |data| {
+                let mut stream = Stream::empty();
+                stream.write_value(Legacy(data)).unwrap();
+                stream
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("write_to", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| {
+                let mut stream = Stream::empty();
+                stream.write_value(data).unwrap();
+                stream
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, primitives, bytes, nested);
+criterion_main!(benches);
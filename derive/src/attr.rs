@@ -0,0 +1,179 @@
+use syn::meta::ParseNestedMeta;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{Attribute, Expr, Ident, LitByteStr, LitStr, Member, Token, Type, parse_quote};
+
+/*
+#[stream(..)] 字段属性,表达式以字符串形式给出,可引用之前已读取的字段
+ */
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub count: Option<Expr>,
+    pub byte_len: Option<Expr>,
+    pub len_of: Option<Ident>,
+    pub offset_of: Option<Ident>,
+    pub crc32_of: Option<Ident>,
+    pub assert: Option<LitStr>,
+    pub condition: Option<Expr>,
+    pub args: Option<Expr>,
+    pub endian: Option<Ident>,
+    pub at: Option<Expr>,
+    pub relative_to: Option<Expr>,
+}
+
+/*
+#[stream(..)] struct/enum 属性
+ */
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub magic: Option<LitByteStr>,
+    pub args: Option<Punctuated<Arg, Token![,]>>,
+}
+
+/*
+#[stream(args = "version: u16, kind: u8")] 中的一个参数
+ */
+pub struct Arg {
+    pub ident: Ident,
+    pub ty: Type,
+}
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(Arg { ident, ty })
+    }
+}
+
+/*
+读取时字段都是局部变量,表达式中的 self.flags 改写成 flags
+ */
+struct StripSelf;
+impl VisitMut for StripSelf {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Field(field) = expr
+            && let Expr::Path(path) = &*field.base
+            && path.path.is_ident("self")
+            && let Member::Named(ident) = &field.member
+        {
+            *expr = parse_quote!(#ident);
+            return;
+        }
+        syn::visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+pub fn parse_expr(value: &LitStr) -> syn::Result<Expr> {
+    let mut expr = value.parse()?;
+    StripSelf.visit_expr_mut(&mut expr);
+    Ok(expr)
+}
+
+fn expr(meta: &ParseNestedMeta) -> syn::Result<Expr> {
+    let value: LitStr = meta.value()?.parse()?;
+    parse_expr(&value)
+}
+
+fn ident(meta: &ParseNestedMeta) -> syn::Result<Ident> {
+    let value: LitStr = meta.value()?.parse()?;
+    value.parse()
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field_attrs = FieldAttrs::default();
+        for attr in attrs {
+            if !attr.path().is_ident("stream") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("count") {
+                    field_attrs.count = Some(expr(&meta)?);
+                } else if meta.path.is_ident("byte_len") {
+                    field_attrs.byte_len = Some(expr(&meta)?);
+                } else if meta.path.is_ident("len_of") {
+                    field_attrs.len_of = Some(ident(&meta)?);
+                } else if meta.path.is_ident("offset_of") {
+                    field_attrs.offset_of = Some(ident(&meta)?);
+                } else if meta.path.is_ident("crc32_of") {
+                    field_attrs.crc32_of = Some(ident(&meta)?);
+                } else if meta.path.is_ident("assert") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    parse_expr(&value)?;
+                    field_attrs.assert = Some(value);
+                } else if meta.path.is_ident("if") {
+                    field_attrs.condition = Some(expr(&meta)?);
+                } else if meta.path.is_ident("args") {
+                    field_attrs.args = Some(expr(&meta)?);
+                } else if meta.path.is_ident("at") {
+                    field_attrs.at = Some(expr(&meta)?);
+                } else if meta.path.is_ident("relative_to") {
+                    field_attrs.relative_to = Some(expr(&meta)?);
+                } else if meta.path.is_ident("big") {
+                    field_attrs.endian = Some(Ident::new("Big", meta.path.span()));
+                } else if meta.path.is_ident("little") {
+                    field_attrs.endian = Some(Ident::new("Little", meta.path.span()));
+                } else {
+                    return Err(meta.error("unsupported stream attribute"));
+                }
+                Ok(())
+            })?;
+            if field_attrs.count.is_some() && field_attrs.byte_len.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "count and byte_len can not be used together",
+                ));
+            }
+            if field_attrs.relative_to.is_some() && field_attrs.at.is_none() {
+                return Err(syn::Error::new_spanned(attr, "relative_to requires at"));
+            }
+            if field_attrs.is_patch() && field_attrs.condition.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "len_of, offset_of and crc32_of can not be used with if",
+                ));
+            }
+            if field_attrs.args.is_some()
+                && (field_attrs.count.is_some() || field_attrs.byte_len.is_some())
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "args can not be used with count or byte_len",
+                ));
+            }
+        }
+        Ok(field_attrs)
+    }
+    /*
+    写入时先占位,等引用的字段写完后再回填
+     */
+    pub fn is_patch(&self) -> bool {
+        self.len_of.is_some() || self.offset_of.is_some() || self.crc32_of.is_some()
+    }
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container_attrs = ContainerAttrs::default();
+        for attr in attrs {
+            if !attr.path().is_ident("stream") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("magic") {
+                    container_attrs.magic = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("args") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    container_attrs.args = Some(value.parse_with(Punctuated::parse_terminated)?);
+                } else {
+                    return Err(meta.error("unsupported stream attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(container_attrs)
+    }
+}
@@ -0,0 +1,149 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    Data, DeriveInput, Expr, ExprLit, ExprRange, Fields, Ident, Lit, RangeLimits, Token, Type,
+};
+
+/*
+#[bits(kind: u8 = 0..3)] 中的一个位段,range 为 start..end、start..=end 或单个 bit
+ */
+struct BitField {
+    ident: Ident,
+    ty: Type,
+    range: Expr,
+}
+impl Parse for BitField {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let range = input.parse()?;
+        Ok(BitField { ident, ty, range })
+    }
+}
+
+fn bit_index(expr: &Expr) -> syn::Result<u32> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(value),
+            ..
+        }) => value.base10_parse(),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "bit index must be integer literal",
+        )),
+    }
+}
+
+fn bit_range(range: &Expr) -> syn::Result<(u32, u32)> {
+    match range {
+        Expr::Range(ExprRange {
+            start: Some(start),
+            limits,
+            end: Some(end),
+            ..
+        }) => {
+            let start = bit_index(start)?;
+            let end = match limits {
+                RangeLimits::HalfOpen(_) => bit_index(end)?,
+                RangeLimits::Closed(_) => bit_index(end)? + 1,
+            };
+            Ok((start, end))
+        }
+        _ => {
+            let start = bit_index(range)?;
+            Ok((start, start + 1))
+        }
+    }
+}
+
+fn container_bits(ty: &Type) -> Option<u32> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    match path.path.get_ident()?.to_string().as_str() {
+        "u8" => Some(8),
+        "u16" => Some(16),
+        "u32" => Some(32),
+        "u64" => Some(64),
+        _ => None,
+    }
+}
+
+pub fn bit_fields(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let container = match &ast.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ast,
+                    "BitFields Only added in Struct(u8/u16/u32/u64)!",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ast,
+                "BitFields Only added in Struct(u8/u16/u32/u64)!",
+            ));
+        }
+    };
+    let bits = container_bits(container)
+        .ok_or_else(|| syn::Error::new_spanned(container, "container must be u8/u16/u32/u64"))?;
+    let mut accessors = vec![];
+    for attr in &ast.attrs {
+        if !attr.path().is_ident("bits") {
+            continue;
+        }
+        let fields = attr.parse_args_with(Punctuated::<BitField, Token![,]>::parse_terminated)?;
+        for field in fields {
+            let (start, end) = bit_range(&field.range)?;
+            if start >= end || end > bits {
+                return Err(syn::Error::new_spanned(
+                    &field.range,
+                    format!("bit range must be inside 0..{}", bits),
+                ));
+            }
+            let mask = Literal::u64_unsuffixed(u64::MAX >> (64 - (end - start)));
+            let shift = Literal::u32_unsuffixed(start);
+            let ident = &field.ident;
+            let ty = &field.ty;
+            let setter = format_ident!("set_{}", ident);
+            let is_bool = matches!(ty, Type::Path(path) if path.path.is_ident("bool"));
+            let get = if is_bool {
+                quote! { (self.0 >> #shift) & #mask != 0 }
+            } else {
+                quote! { ((self.0 >> #shift) & #mask) as #ty }
+            };
+            accessors.push(quote! {
+                pub fn #ident(&self) -> #ty {
+                    #get
+                }
+                pub fn #setter(&mut self, value: #ty) -> &mut Self {
+                    self.0 = (self.0 & !(#mask << #shift)) | (((value as #container) & #mask) << #shift);
+                    self
+                }
+            });
+        }
+    }
+    Ok(quote! {
+        impl #name {
+            #(#accessors)*
+        }
+        impl fast_stream::bytes::ValueRead for #name {
+            fn read(stream: &mut fast_stream::stream::Stream) -> std::io::Result<Self> {
+                use fast_stream::bytes::Bytes;
+                Ok(Self(stream.read_value()?))
+            }
+        }
+        impl fast_stream::bytes::ValueWrite for #name {
+            fn write_to(self, stream: &mut fast_stream::stream::Stream) -> std::io::Result<()> {
+                fast_stream::bytes::ValueWrite::write_to(self.0, stream)
+            }
+        }
+    })
+}
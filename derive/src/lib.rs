@@ -1,6 +1,10 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, ExprLit, Fields, Meta, Type};
+use syn::{parse_macro_input, Data, DeriveInput, ExprLit, Fields, Type};
+
+mod attr;
+mod bits;
+mod value;
 
 ///
 /// #[repr(u32)]
@@ -13,25 +17,21 @@ use syn::{parse_macro_input, Data, DeriveInput, ExprLit, Fields, Meta, Type};
 /// }
 ///  let v: u32 = Cpu::Arm.into();
 ///  let cpu: Cpu = (3|4).into();
+///
+/// 没有 Unknown(..) 这类兜底 variant 时生成 TryFrom,无法匹配返回 UnknownValue 而不是 panic
+///  let cpu = Cpu::try_from(5)?;
 #[proc_macro_derive(NumToEnum)]
 pub fn num_to_enum(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     let enum_name = &ast.ident;
     if let Data::Enum(data_enum) = &ast.data {
-        let enum_base_type = ast
-            .attrs
-            .iter()
-            .find_map(|attr| {
-                if attr.path().is_ident("repr") {
-                    if let Meta::List(meta_list) = &attr.meta {
-                        return Some(meta_list.tokens.clone());
-                    }
-                }
-                Some(quote! {u32})
-            })
-            .unwrap();
+        let enum_base_type = match value::repr_type(&ast) {
+            Ok(enum_base_type) => enum_base_type,
+            Err(e) => return e.to_compile_error().into(),
+        };
         let mut into_fields = vec![];
         let mut from_fields = vec![];
+        let mut catch_all = false;
         for variant in &data_enum.variants {
             let field_name = &variant.ident;
             match &variant.fields {
@@ -47,6 +47,7 @@ pub fn num_to_enum(input: TokenStream) -> TokenStream {
                             from_fields.push(quote! {
                                value => #enum_name::#field_name(value),
                             });
+                            catch_all = true;
                         }
                     }
                 }
@@ -71,6 +72,34 @@ pub fn num_to_enum(input: TokenStream) -> TokenStream {
                 }
             }
         }
+        let from_impl = if catch_all {
+            quote! {
+                impl From<#enum_base_type> for #enum_name{
+                    fn from(value: #enum_base_type) -> Self {
+                        match value {
+                            #(#from_fields)*
+                        }
+                    }
+                }
+            }
+        } else {
+            quote! {
+                impl TryFrom<#enum_base_type> for #enum_name{
+                    type Error = fast_stream::enums::UnknownValue;
+                    fn try_from(value: #enum_base_type) -> Result<Self, Self::Error> {
+                        Ok(match value {
+                            #(#from_fields)*
+                            _ => {
+                                return Err(fast_stream::enums::UnknownValue {
+                                    name: stringify!(#enum_name),
+                                    value: value as i128,
+                                });
+                            }
+                        })
+                    }
+                }
+            }
+        };
         let expanded = quote! {
             impl Into<#enum_base_type> for #enum_name {
                 fn into(self) -> #enum_base_type {
@@ -79,14 +108,7 @@ pub fn num_to_enum(input: TokenStream) -> TokenStream {
                     }
                 }
             }
-            impl From<#enum_base_type> for #enum_name{
-                fn from(value: #enum_base_type) -> Self {
-                    match value {
-                        #(#from_fields)*
-                        _ => panic!("can not match {}",value)
-                    }
-                }
-            }
+            #from_impl
         };
         TokenStream::from(expanded)
     } else {
@@ -95,3 +117,116 @@ pub fn num_to_enum(input: TokenStream) -> TokenStream {
             .into()
     }
 }
+
+///
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// pub struct Header {
+///     magic: u32,
+///     version: u16,
+///     name: String,
+/// }
+///  let header: Header = stream.read_value()?;
+///  stream.write_value(header)?;
+///
+/// Vec 字段默认带 u64 长度前缀,可以改为由之前的字段决定个数或字节长度
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// pub struct Table {
+///     n_entries: u32,
+///     size: u32,
+///     #[stream(count = "n_entries")]
+///     entries: Vec<u64>,
+///     #[stream(byte_len = "size")]
+///     names: Vec<String>,
+/// }
+///
+/// 写入时回填的字段:引用字段的字节长度,相对起始位置的偏移,crc32
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// pub struct Entry {
+///     #[stream(offset_of = "data")]
+///     offset: u32,
+///     #[stream(len_of = "data")]
+///     size: u32,
+///     #[stream(crc32_of = "data")]
+///     crc32: u32,
+///     data: Vec<u8>,
+/// }
+///
+/// 读取时校验 magic 与字段断言,失败返回 InvalidData 并带上 struct、字段与偏移
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// #[stream(magic = b"PK\x03\x04")]
+/// pub struct LocalFile {
+///     #[stream(assert = "version <= 63")]
+///     version: u16,
+/// }
+///
+/// 条件字段:条件成立时读取 Option 的内容,不成立为 None,写入时 None 不占空间
+/// 通过 args 把上下文参数传给 ValueReadArgs::read_args
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// #[stream(args = "version: u16")]
+/// pub struct Entry {
+///     flags: u8,
+///     #[stream(if = "self.flags & 1 != 0")]
+///     extra: Option<u32>,
+///     #[stream(if = "version >= 2")]
+///     checksum: Option<u16>,
+/// }
+///  let entry: Entry = stream.read_value_args(version)?;
+///
+/// 单个字段可以覆盖 stream 的字节序
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// pub struct FatArch {
+///     #[stream(big)]
+///     cpu_type: u32,
+///     #[stream(little)]
+///     offset: u32,
+/// }
+///
+/// at 字段跳转到偏移处读取后恢复位置,relative_to 可以是 start(struct 起始)、current(字段位置)或表达式
+/// 只用于 ValueRead,ValueWrite 遇到 at 字段会编译报错
+/// #[derive(Debug, ValueRead)]
+/// pub struct Symbol {
+///     name_offset: u32,
+///     #[stream(at = "name_offset", relative_to = "start")]
+///     name: String,
+/// }
+///
+/// enum 先读写 #[repr] 宽度的 tag,再读写对应 variant 的字段
+/// #[repr(u8)]
+/// #[derive(Debug, ValueRead, ValueWrite)]
+/// pub enum Command {
+///     Segment { name: String, size: u64 } = 1,
+///     Symtab(u32, u32) = 2,
+///     End = 0xFF,
+/// }
+#[proc_macro_derive(ValueRead, attributes(stream))]
+pub fn value_read(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    value::value_read(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(ValueWrite, attributes(stream))]
+pub fn value_write(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    value::value_write(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+///
+/// 把 u8/u16/u32/u64 的位段映射为带类型的访问方法,整体按 stream 的字节序读写
+/// #[derive(Debug, Clone, Copy, BitFields)]
+/// #[bits(kind: u8 = 0..3, enabled: bool = 3)]
+/// #[bits(level: u16 = 4..=12)]
+/// pub struct Flags(u32);
+///  let flags: Flags = stream.read_value()?;
+///  let kind = flags.kind();
+///  flags.set_enabled(true);
+#[proc_macro_derive(BitFields, attributes(bits))]
+pub fn bit_fields(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    bits::bit_fields(&ast)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -0,0 +1,486 @@
+use crate::attr::{ContainerAttrs, FieldAttrs, parse_expr};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{Data, DeriveInput, Fields, Generics, Ident, Token};
+
+/*
+每个类型参数都加上 bound,字段类型用到的参数才能读写
+ */
+fn bound_generics(generics: &Generics, bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #param: #bound });
+    }
+    generics
+}
+
+/*
+字段在读取时绑定成同名局部变量,tuple struct 使用 __field0.. 命名
+ */
+fn field_idents(fields: &Fields) -> Vec<Ident> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("__field{}", index),
+        })
+        .collect()
+}
+
+fn construct(path: TokenStream, fields: &Fields, idents: &[Ident]) -> TokenStream {
+    match fields {
+        Fields::Named(_) => quote! { #path { #(#idents),* } },
+        Fields::Unnamed(_) => quote! { #path ( #(#idents),* ) },
+        Fields::Unit => quote! { #path },
+    }
+}
+
+/*
+owner 用于错误信息,例如 Header 或 Command::Segment
+ */
+fn read_fields(owner: &str, fields: &Fields, idents: &[Ident]) -> syn::Result<TokenStream> {
+    let mut reads = vec![];
+    for (field, ident) in fields.iter().zip(idents) {
+        let ty = &field.ty;
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let offset = format_ident!("__offset_{}", ident);
+        /*
+        read 是 io::Result 表达式,最后再加 ?,闭包内直接返回避免 Ok(..?)
+         */
+        let read = if let Some(count) = &attrs.count {
+            quote! { __stream.read_values((#count) as u64) }
+        } else if let Some(byte_len) = &attrs.byte_len {
+            quote! { __stream.read_values_size((#byte_len) as u64) }
+        } else if let Some(args) = &attrs.args {
+            quote! { __stream.read_value_args(#args) }
+        } else {
+            quote! { __stream.read_value() }
+        };
+        let read = match &attrs.endian {
+            Some(endian) => quote! {
+                __stream.with_scoped_endian(
+                    fast_stream::endian::Endian::#endian,
+                    |__stream| #read,
+                )
+            },
+            None => read,
+        };
+        /*
+        at 字段跳转到偏移读取后恢复位置,不占用顺序读取的字节
+        relative_to 为 start 时相对 struct 起始位置,current 时相对字段所在位置,否则为表达式
+         */
+        let read = match &attrs.at {
+            Some(at) => {
+                let base = match &attrs.relative_to {
+                    Some(syn::Expr::Path(path)) if path.path.is_ident("start") => {
+                        quote! { __start }
+                    }
+                    Some(syn::Expr::Path(path)) if path.path.is_ident("current") => {
+                        quote! { #offset }
+                    }
+                    Some(relative_to) => quote! { (#relative_to) as u64 },
+                    None => quote! { 0 },
+                };
+                quote! {
+                    __stream.read_at(#base + (#at) as u64, |__stream| #read)
+                }
+            }
+            None => read,
+        };
+        let read = match &attrs.condition {
+            Some(condition) => quote! {
+                if #condition { Some(#read?) } else { None }
+            },
+            None => quote! { #read? },
+        };
+        reads.push(quote! {
+            let #offset = __stream.stream_position()?;
+            let #ident: #ty = #read;
+        });
+        if let Some(assert) = &attrs.assert {
+            let expr = parse_expr(assert)?;
+            let message = format!(
+                "{}.{}: assert `{}` failed at offset ",
+                owner,
+                ident,
+                assert.value()
+            );
+            reads.push(quote! {
+                if !(#expr) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{}{}", #message, #offset),
+                    ));
+                }
+            });
+        }
+    }
+    Ok(quote! { #(#reads)* })
+}
+
+/*
+magic 在 struct/enum 的最前面,读取时校验不一致报错
+ */
+fn read_magic(owner: &str, attrs: &ContainerAttrs) -> TokenStream {
+    let Some(magic) = &attrs.magic else {
+        return quote! {};
+    };
+    let size = magic.value().len() as u64;
+    let message = format!("{}: magic mismatch at offset ", owner);
+    quote! {
+        let __offset = __stream.stream_position()?;
+        let __magic = __stream.read_exact_size(#size)?;
+        if __magic.as_slice() != #magic {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}{}, expected {:?} found {:?}", #message, __offset, #magic, __magic),
+            ));
+        }
+    }
+}
+
+fn write_magic(attrs: &ContainerAttrs) -> TokenStream {
+    let Some(magic) = &attrs.magic else {
+        return quote! {};
+    };
+    quote! {
+        std::io::Write::write_all(__stream, #magic)?;
+    }
+}
+
+/*
+count/byte_len 字段的长度由其它字段描述,写入时不带长度前缀
+len_of/offset_of/crc32_of 字段先写入占位,所有字段写完后根据记录的起止位置回填
+ */
+fn write_fields(
+    fields: &Fields,
+    idents: &[Ident],
+    values: Vec<TokenStream>,
+) -> syn::Result<TokenStream> {
+    let mut writes = vec![];
+    let mut patches = vec![];
+    for ((field, ident), value) in fields.iter().zip(idents).zip(values) {
+        let ty = &field.ty;
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.at.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "ValueWrite can not write at field, write the pointed value separately",
+            ));
+        }
+        let start = format_ident!("__start_{}", ident);
+        let end = format_ident!("__end_{}", ident);
+        let (source, value) = if attrs.condition.is_some() {
+            (Some(value), quote! { __value })
+        } else {
+            (None, value)
+        };
+        let write = if attrs.is_patch() {
+            let placeholder = format_ident!("__placeholder_{}", ident);
+            let patch_value = if let Some(target) = &attrs.len_of {
+                let target_start = format_ident!("__start_{}", target);
+                let target_end = format_ident!("__end_{}", target);
+                quote! { #target_end - #target_start }
+            } else if let Some(target) = &attrs.offset_of {
+                let target_start = format_ident!("__start_{}", target);
+                quote! { #target_start - __start }
+            } else if let Some(target) = &attrs.crc32_of {
+                let target_start = format_ident!("__start_{}", target);
+                let target_end = format_ident!("__end_{}", target);
+                quote! { __stream.crc32_range(#target_start, #target_end)? }
+            } else {
+                unreachable!()
+            };
+            let message = format!("{} value {{}} overflow {}", ident, quote! { #ty });
+            patches.push(quote! {
+                let __value = #patch_value;
+                let __value = <#ty>::try_from(__value).map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(#message, __value),
+                    )
+                })?;
+                __stream.patch(#placeholder, __value)?;
+            });
+            quote! {
+                let _ = #value;
+                let #placeholder = __stream.reserve::<#ty>()?;
+            }
+        } else if attrs.count.is_some() || attrs.byte_len.is_some() {
+            quote! { __stream.write_values(#value)?; }
+        } else {
+            quote! { __stream.write_value(#value)?; }
+        };
+        let write = match source {
+            Some(source) => quote! {
+                if let Some(__value) = #source {
+                    #write
+                }
+            },
+            None => write,
+        };
+        /*
+        直接写入目标 stream,出错时也要恢复字节序,占位值需要带出闭包
+         */
+        let write = match &attrs.endian {
+            Some(endian) if attrs.is_patch() => {
+                let placeholder = format_ident!("__placeholder_{}", ident);
+                quote! {
+                    let #placeholder = __stream.with_scoped_endian(
+                        fast_stream::endian::Endian::#endian,
+                        |__stream| {
+                            #write
+                            Ok(#placeholder)
+                        },
+                    )?;
+                }
+            }
+            Some(endian) => quote! {
+                __stream.with_scoped_endian(fast_stream::endian::Endian::#endian, |__stream| {
+                    #write
+                    Ok(())
+                })?;
+            },
+            None => write,
+        };
+        writes.push(quote! {
+            let #start = __stream.stream_position()?;
+            #write
+            let #end = __stream.stream_position()?;
+        });
+    }
+    Ok(quote! {
+        #(#writes)*
+        #(#patches)*
+    })
+}
+
+/*
+enum 的 tag 类型取自 #[repr(..)],没有整数 repr 时默认 u32
+u128 超出 i128 的值在 UnknownValue 中会回绕
+ */
+pub(crate) fn repr_type(ast: &DeriveInput) -> syn::Result<Ident> {
+    for attr in &ast.attrs {
+        if attr.path().is_ident("repr") {
+            let reprs = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+            if let Some(repr) = reprs.into_iter().find(|repr| {
+                matches!(
+                    repr.to_string().as_str(),
+                    "u8" | "i8"
+                        | "u16"
+                        | "i16"
+                        | "u32"
+                        | "i32"
+                        | "u64"
+                        | "i64"
+                        | "u128"
+                        | "i128"
+                        | "usize"
+                        | "isize"
+                )
+            }) {
+                return Ok(repr);
+            }
+        }
+    }
+    Ok(format_ident!("u32"))
+}
+
+/*
+没有显式指定的 discriminant 按 rust 规则在上一个的基础上 +1
+ */
+fn discriminants(variants: &Punctuated<syn::Variant, Token![,]>) -> Vec<TokenStream> {
+    let mut previous: Option<TokenStream> = None;
+    variants
+        .iter()
+        .map(|variant| {
+            let value = match (&variant.discriminant, &previous) {
+                (Some((_, expr)), _) => quote! { (#expr) },
+                (None, Some(previous)) => quote! { (#previous + 1) },
+                (None, None) => quote! { 0 },
+            };
+            previous = Some(value.clone());
+            value
+        })
+        .collect()
+}
+
+pub fn value_read(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let generics = bound_generics(
+        &ast.generics,
+        quote! { fast_stream::bytes::ValueRead + 'static },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let container_attrs = ContainerAttrs::parse(&ast.attrs)?;
+    let magic = read_magic(&name.to_string(), &container_attrs);
+    let body = match &ast.data {
+        Data::Struct(data_struct) => {
+            let idents = field_idents(&data_struct.fields);
+            let reads = read_fields(&name.to_string(), &data_struct.fields, &idents)?;
+            let construct = construct(quote! { Self }, &data_struct.fields, &idents);
+            quote! {
+                #reads
+                Ok(#construct)
+            }
+        }
+        Data::Enum(data_enum) => {
+            let repr = repr_type(ast)?;
+            let discriminants = discriminants(&data_enum.variants);
+            let mut arms = vec![];
+            for (variant, discriminant) in data_enum.variants.iter().zip(discriminants) {
+                let variant_name = &variant.ident;
+                let idents = field_idents(&variant.fields);
+                let owner = format!("{}::{}", name, variant_name);
+                let reads = read_fields(&owner, &variant.fields, &idents)?;
+                let construct = construct(quote! { Self::#variant_name }, &variant.fields, &idents);
+                arms.push(quote! {
+                    __tag if __tag == #discriminant as #repr => {
+                        #reads
+                        Ok(#construct)
+                    }
+                });
+            }
+            quote! {
+                let __tag: #repr = __stream.read_value()?;
+                match __tag {
+                    #(#arms)*
+                    __tag => Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{} can not match tag {}", stringify!(#name), __tag),
+                    )),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ast,
+                "ValueRead Only added in Struct or Enum!",
+            ));
+        }
+    };
+    let Some(args) = &container_attrs.args else {
+        return Ok(quote! {
+            impl #impl_generics fast_stream::bytes::ValueRead for #name #ty_generics #where_clause {
+                fn read(__stream: &mut fast_stream::stream::Stream) -> std::io::Result<Self> {
+                    use fast_stream::bytes::Bytes;
+                    use std::io::Seek;
+                    let __start = __stream.stream_position()?;
+                    #magic
+                    #body
+                }
+            }
+        });
+    };
+    /*
+    带参数时只实现 ValueReadArgs,单个参数直接使用其类型,多个参数组成 tuple
+     */
+    let idents: Vec<_> = args.iter().map(|arg| &arg.ident).collect();
+    let types: Vec<_> = args.iter().map(|arg| &arg.ty).collect();
+    let (args_type, bind) = if args.len() == 1 {
+        (
+            quote! { #(#types)* },
+            quote! { let #(#idents)*: #(#types)* = __args; },
+        )
+    } else {
+        (
+            quote! { (#(#types),*) },
+            quote! { let (#(#idents),*): (#(#types),*) = __args; },
+        )
+    };
+    Ok(quote! {
+        impl #impl_generics fast_stream::bytes::ValueReadArgs<#args_type> for #name #ty_generics #where_clause {
+            fn read_args(
+                __stream: &mut fast_stream::stream::Stream,
+                __args: #args_type,
+            ) -> std::io::Result<Self> {
+                use fast_stream::bytes::Bytes;
+                use std::io::Seek;
+                #bind
+                let __start = __stream.stream_position()?;
+                #magic
+                #body
+            }
+        }
+    })
+}
+
+pub fn value_write(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let generics = bound_generics(
+        &ast.generics,
+        quote! { fast_stream::bytes::ValueWrite + 'static },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let magic = write_magic(&ContainerAttrs::parse(&ast.attrs)?);
+    let body = match &ast.data {
+        Data::Struct(data_struct) => {
+            let idents = field_idents(&data_struct.fields);
+            let values = data_struct
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| match &field.ident {
+                    Some(ident) => quote! { self.#ident },
+                    None => {
+                        let index = syn::Index::from(index);
+                        quote! { self.#index }
+                    }
+                })
+                .collect();
+            write_fields(&data_struct.fields, &idents, values)?
+        }
+        Data::Enum(data_enum) => {
+            let repr = repr_type(ast)?;
+            let discriminants = discriminants(&data_enum.variants);
+            let mut arms = vec![];
+            for (variant, discriminant) in data_enum.variants.iter().zip(discriminants) {
+                let variant_name = &variant.ident;
+                let idents = field_idents(&variant.fields);
+                let pattern = construct(quote! { Self::#variant_name }, &variant.fields, &idents);
+                let values = idents.iter().map(|ident| quote! { #ident }).collect();
+                let writes = write_fields(&variant.fields, &idents, values)?;
+                arms.push(quote! {
+                    #pattern => {
+                        __stream.write_value(#discriminant as #repr)?;
+                        #writes
+                    }
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ast,
+                "ValueWrite Only added in Struct or Enum!",
+            ));
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics fast_stream::bytes::ValueWrite for #name #ty_generics #where_clause {
+            fn write_to(
+                self,
+                __stream: &mut fast_stream::stream::Stream,
+            ) -> std::io::Result<()> {
+                use std::io::Seek;
+                let __start = __stream.stream_position()?;
+                #magic
+                #body
+                Ok(())
+            }
+        }
+    })
+}
@@ -12,6 +12,8 @@ impl Align for Stream {
         let remainder = len % align;
         if remainder != 0 {
             let padding = align - remainder;
+            #[cfg(feature = "file")]
+            self.spill_check(len + padding)?;
             let padding_bytes = vec![0_u8; padding as usize];
             self.data.borrow_mut().write_all(&padding_bytes)?;
         }
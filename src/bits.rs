@@ -0,0 +1,297 @@
+use crate::stream::Stream;
+use std::io;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/*
+Msb: 先读写字节的最高位,先读到的 bit 是结果的高位(视频编码头等)
+Lsb: 先读写字节的最低位,先读到的 bit 是结果的低位(deflate 等)
+ */
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BitOrder {
+    Msb,
+    Lsb,
+}
+
+#[derive(Debug)]
+pub struct BitReader<'a> {
+    stream: &'a mut Stream,
+    order: BitOrder,
+    byte: u8,
+    remaining: u8,
+}
+impl<'a> BitReader<'a> {
+    pub fn new(stream: &'a mut Stream, order: BitOrder) -> Self {
+        Self {
+            stream,
+            order,
+            byte: 0,
+            remaining: 0,
+        }
+    }
+    pub fn read_bit(&mut self) -> io::Result<bool> {
+        if self.remaining == 0 {
+            let mut byte = [0_u8; 1];
+            self.stream.read_exact(&mut byte)?;
+            self.byte = byte[0];
+            self.remaining = 8;
+        }
+        self.remaining -= 1;
+        let bit = match self.order {
+            BitOrder::Msb => (self.byte >> self.remaining) & 1,
+            BitOrder::Lsb => (self.byte >> (7 - self.remaining)) & 1,
+        };
+        Ok(bit == 1)
+    }
+    pub fn read_bits(&mut self, count: u32) -> io::Result<u64> {
+        if count > 64 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("can not read {} bits into u64", count),
+            ));
+        }
+        let mut value = 0_u64;
+        for index in 0..count {
+            let bit = self.read_bit()? as u64;
+            match self.order {
+                BitOrder::Msb => value = (value << 1) | bit,
+                BitOrder::Lsb => value |= bit << index,
+            }
+        }
+        Ok(value)
+    }
+    /*
+    丢弃当前字节剩余的 bit,下一次从新的字节开始读取
+     */
+    pub fn align_to_byte(&mut self) {
+        self.remaining = 0;
+    }
+    pub fn is_aligned(&self) -> bool {
+        self.remaining == 0
+    }
+    /*
+    统计遇到 1 之前 0 的个数
+     */
+    pub fn read_unary(&mut self) -> io::Result<u64> {
+        let mut count = 0;
+        while !self.read_bit()? {
+            count += 1;
+        }
+        Ok(count)
+    }
+    pub fn read_exp_golomb(&mut self) -> io::Result<u64> {
+        let zeros = self.read_unary()?;
+        if zeros > 63 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("exp-golomb prefix {} overflow u64", zeros),
+            ));
+        }
+        let suffix = self.read_bits(zeros as u32)?;
+        Ok((1_u64 << zeros) - 1 + suffix)
+    }
+    pub fn read_signed_exp_golomb(&mut self) -> io::Result<i64> {
+        let value = self.read_exp_golomb()?;
+        if value & 1 == 0 {
+            return Ok(-((value / 2) as i64));
+        }
+        i64::try_from(value.div_ceil(2)).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("signed exp-golomb value {} overflow i64", value),
+            )
+        })
+    }
+    pub fn stream(&mut self) -> &mut Stream {
+        self.stream
+    }
+}
+
+#[derive(Debug)]
+pub struct BitWriter<'a> {
+    stream: &'a mut Stream,
+    order: BitOrder,
+    byte: u8,
+    used: u8,
+}
+impl<'a> BitWriter<'a> {
+    pub fn new(stream: &'a mut Stream, order: BitOrder) -> Self {
+        Self {
+            stream,
+            order,
+            byte: 0,
+            used: 0,
+        }
+    }
+    pub fn write_bit(&mut self, bit: bool) -> io::Result<()> {
+        let bit = bit as u8;
+        match self.order {
+            BitOrder::Msb => self.byte |= bit << (7 - self.used),
+            BitOrder::Lsb => self.byte |= bit << self.used,
+        }
+        self.used += 1;
+        if self.used == 8 {
+            self.stream.write_all(&[self.byte])?;
+            self.byte = 0;
+            self.used = 0;
+        }
+        Ok(())
+    }
+    pub fn write_bits(&mut self, value: u64, count: u32) -> io::Result<()> {
+        if count > 64 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("can not write {} bits from u64", count),
+            ));
+        }
+        for index in 0..count {
+            let shift = match self.order {
+                BitOrder::Msb => count - 1 - index,
+                BitOrder::Lsb => index,
+            };
+            self.write_bit((value >> shift) & 1 == 1)?;
+        }
+        Ok(())
+    }
+    /*
+    当前字节剩余的 bit 补 0 后写入
+     */
+    pub fn align_to_byte(&mut self) -> io::Result<()> {
+        if self.used > 0 {
+            self.stream.write_all(&[self.byte])?;
+            self.byte = 0;
+            self.used = 0;
+        }
+        Ok(())
+    }
+    pub fn is_aligned(&self) -> bool {
+        self.used == 0
+    }
+    pub fn write_unary(&mut self, value: u64) -> io::Result<()> {
+        for _ in 0..value {
+            self.write_bit(false)?;
+        }
+        self.write_bit(true)
+    }
+    pub fn write_exp_golomb(&mut self, value: u64) -> io::Result<()> {
+        let value = value.checked_add(1).ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            "exp-golomb value overflow u64",
+        ))?;
+        let bits = 64 - value.leading_zeros();
+        for _ in 1..bits {
+            self.write_bit(false)?;
+        }
+        self.write_bit(true)?;
+        self.write_bits(value & !(1 << (bits - 1)), bits - 1)
+    }
+    pub fn write_signed_exp_golomb(&mut self, value: i64) -> io::Result<()> {
+        let value = if value > 0 {
+            (value as u64) * 2 - 1
+        } else {
+            value.unsigned_abs().checked_mul(2).ok_or(Error::new(
+                ErrorKind::InvalidInput,
+                "signed exp-golomb value overflow u64",
+            ))?
+        };
+        self.write_exp_golomb(value)
+    }
+    /*
+    补齐最后一个字节,drop 时也会补齐但会忽略错误
+     */
+    pub fn finish(mut self) -> io::Result<()> {
+        self.align_to_byte()
+    }
+    pub fn stream(&mut self) -> &mut Stream {
+        self.stream
+    }
+}
+impl Drop for BitWriter<'_> {
+    fn drop(&mut self) {
+        let _ = self.align_to_byte();
+    }
+}
+impl Stream {
+    pub fn bit_reader(&mut self, order: BitOrder) -> BitReader<'_> {
+        BitReader::new(self, order)
+    }
+    pub fn bit_writer(&mut self, order: BitOrder) -> BitWriter<'_> {
+        BitWriter::new(self, order)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::bits::BitOrder;
+    use crate::stream::Stream;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_read_bits() {
+        let mut stream: Stream = vec![0b1011_0010, 0b0110_0000].into();
+        let mut reader = stream.bit_reader(BitOrder::Msb);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.read_bits(7).unwrap(), 0b100_1001);
+        reader.align_to_byte();
+        assert!(reader.read_bit().is_err());
+
+        let mut stream: Stream = vec![0b1011_0010, 0b0110_0000].into();
+        let mut reader = stream.bit_reader(BitOrder::Lsb);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b010);
+        assert_eq!(reader.read_bits(7).unwrap(), 0b00_10110);
+        assert_eq!(reader.read_bits(6).unwrap(), 0b011000);
+    }
+    #[test]
+    fn test_exp_golomb() {
+        // 1 010 011 00100 00101
+        let mut stream: Stream = vec![0b1010_0110, 0b0100_0010, 0b1000_0000].into();
+        let mut reader = stream.bit_reader(BitOrder::Msb);
+        for value in 0..5 {
+            assert_eq!(reader.read_exp_golomb().unwrap(), value);
+        }
+        for order in [BitOrder::Msb, BitOrder::Lsb] {
+            let mut stream = Stream::empty();
+            let mut writer = stream.bit_writer(order);
+            writer.write_bits(0x1FFF, 13).unwrap();
+            writer.write_unary(4).unwrap();
+            writer.write_exp_golomb(1000).unwrap();
+            writer.write_signed_exp_golomb(-7).unwrap();
+            writer.write_signed_exp_golomb(7).unwrap();
+            writer.finish().unwrap();
+            stream.seek_start().unwrap();
+            let mut reader = stream.bit_reader(order);
+            assert_eq!(reader.read_bits(13).unwrap(), 0x1FFF);
+            assert_eq!(reader.read_unary().unwrap(), 4);
+            assert_eq!(reader.read_exp_golomb().unwrap(), 1000);
+            assert_eq!(reader.read_signed_exp_golomb().unwrap(), -7);
+            assert_eq!(reader.read_signed_exp_golomb().unwrap(), 7);
+        }
+        let mut stream = Stream::empty();
+        let mut writer = stream.bit_writer(BitOrder::Msb);
+        assert_eq!(
+            writer.write_signed_exp_golomb(i64::MIN).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        writer.write_signed_exp_golomb(i64::MIN + 1).unwrap();
+        writer.write_signed_exp_golomb(i64::MAX).unwrap();
+        writer.finish().unwrap();
+        stream.seek_start().unwrap();
+        let mut reader = stream.bit_reader(BitOrder::Msb);
+        assert_eq!(reader.read_signed_exp_golomb().unwrap(), i64::MIN + 1);
+        assert_eq!(reader.read_signed_exp_golomb().unwrap(), i64::MAX);
+    }
+    #[test]
+    fn test_write_bits() {
+        let mut stream = Stream::empty();
+        let mut writer = stream.bit_writer(BitOrder::Msb);
+        writer.write_bits(0b101, 3).unwrap();
+        writer.write_bit(true).unwrap();
+        drop(writer);
+        assert_eq!(stream.copy_data().unwrap(), vec![0b1011_0000]);
+
+        let mut stream = Stream::empty();
+        let mut writer = stream.bit_writer(BitOrder::Lsb);
+        writer.write_bits(0b101, 3).unwrap();
+        writer.write_bits(0b11111, 6).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(stream.copy_data().unwrap(), vec![0b1111_1101, 0b0000_0000]);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+
+const CAPACITY: usize = 8 * 1024;
+const TAKEN: &str = "file taken by into_inner";
+
+fn file(inner: &mut Option<File>) -> &mut File {
+    inner.as_mut().expect(TAKEN)
+}
+
+/*
+带读写缓冲的文件,Data::File 使用
+读缓冲保存 [read_start, read_start + read.len()) 的文件内容
+写缓冲保存从 write_start 开始连续写入、还没有落盘的数据
+同一时间只有一个缓冲有数据,seek 只修改 position,不产生系统调用
+length 记录包含缓冲在内的文件长度,None 时下次用 metadata 重新获取
+inner 只在 into_inner 中取出,取出后不会再读写
+ */
+#[derive(Debug)]
+pub struct BufFile {
+    inner: Option<File>,
+    position: u64,
+    read: Vec<u8>,
+    read_start: u64,
+    write: Vec<u8>,
+    write_start: u64,
+    length: Option<u64>,
+}
+impl BufFile {
+    /*
+    从文件当前位置开始,获取不到位置时从 0 开始
+     */
+    pub fn new(mut inner: File) -> Self {
+        let position = inner.stream_position().unwrap_or(0);
+        let length = inner.metadata().ok().map(|metadata| metadata.len());
+        Self {
+            inner: Some(inner),
+            position,
+            read: Vec::new(),
+            read_start: 0,
+            write: Vec::new(),
+            write_start: 0,
+            length,
+        }
+    }
+    /*
+    还没有落盘的写入数据不在文件中,需要先 flush
+     */
+    pub fn get_ref(&self) -> &File {
+        self.inner.as_ref().expect(TAKEN)
+    }
+    /*
+    先落盘并把文件位置移动到当前位置,之后直接操作文件也不会和缓冲冲突
+     */
+    pub fn get_mut(&mut self) -> io::Result<&mut File> {
+        self.flush_write()?;
+        self.read.clear();
+        file(&mut self.inner).seek(SeekFrom::Start(self.position))?;
+        self.length = None;
+        Ok(file(&mut self.inner))
+    }
+    /*
+    落盘后取出文件,文件位置为当前位置
+     */
+    pub fn into_inner(mut self) -> io::Result<File> {
+        self.flush_write()?;
+        let mut inner = self.inner.take().expect(TAKEN);
+        inner.seek(SeekFrom::Start(self.position))?;
+        Ok(inner)
+    }
+    /*
+    文件长度,包含还没有落盘的数据
+     */
+    pub fn length(&mut self) -> io::Result<u64> {
+        if let Some(length) = self.length {
+            return Ok(length);
+        }
+        let length = file(&mut self.inner).metadata()?.len();
+        self.length = Some(length);
+        Ok(length)
+    }
+    pub fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.flush_write()?;
+        self.read.clear();
+        file(&mut self.inner).set_len(len)?;
+        self.length = Some(len);
+        Ok(())
+    }
+    fn flush_write(&mut self) -> io::Result<()> {
+        if !self.write.is_empty() {
+            file(&mut self.inner).seek(SeekFrom::Start(self.write_start))?;
+            file(&mut self.inner).write_all(&self.write)?;
+            self.write.clear();
+        }
+        Ok(())
+    }
+    fn buffered(&self) -> Option<&[u8]> {
+        let offset = self.position.checked_sub(self.read_start)?;
+        self.read
+            .get(offset as usize..)
+            .filter(|data| !data.is_empty())
+    }
+}
+impl Read for BufFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.flush_write()?;
+        if self.buffered().is_none() {
+            self.read.clear();
+            file(&mut self.inner).seek(SeekFrom::Start(self.position))?;
+            if buf.len() >= CAPACITY {
+                let size = file(&mut self.inner).read(buf)?;
+                self.position += size as u64;
+                return Ok(size);
+            }
+            self.read.resize(CAPACITY, 0);
+            let size = file(&mut self.inner).read(&mut self.read)?;
+            self.read.truncate(size);
+            self.read_start = self.position;
+        }
+        let Some(data) = self.buffered() else {
+            return Ok(0);
+        };
+        let size = data.len().min(buf.len());
+        buf[..size].copy_from_slice(&data[..size]);
+        self.position += size as u64;
+        Ok(size)
+    }
+}
+impl Write for BufFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let length = self.length()?;
+        self.read.clear();
+        if self.write_start + self.write.len() as u64 != self.position {
+            self.flush_write()?;
+        }
+        if self.write.is_empty() {
+            self.write_start = self.position;
+        }
+        if self.write.len() + buf.len() > CAPACITY {
+            self.flush_write()?;
+            if buf.len() >= CAPACITY {
+                file(&mut self.inner).seek(SeekFrom::Start(self.position))?;
+                let size = file(&mut self.inner).write(buf)?;
+                self.position += size as u64;
+                self.length = Some(length.max(self.position));
+                return Ok(size);
+            }
+            self.write_start = self.position;
+        }
+        self.write.extend_from_slice(buf);
+        self.position += buf.len() as u64;
+        self.length = Some(length.max(self.position));
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_write()?;
+        file(&mut self.inner).flush()
+    }
+}
+impl Seek for BufFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.length()?.checked_add_signed(offset),
+        };
+        self.position = position.ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        ))?;
+        Ok(self.position)
+    }
+}
+impl Drop for BufFile {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.flush_write();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::BufFile;
+    use crate::bytes::Bytes;
+    use crate::stream::Stream;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_buf_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        let mut stream = Stream::new(file.try_clone().unwrap().into());
+        for value in 0..10_000_u32 {
+            stream.write_value(value).unwrap();
+        }
+        assert_eq!(stream.length(), 40_000);
+        stream.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(stream.read_value::<u32>().unwrap(), 1);
+        stream.seek(SeekFrom::End(-4)).unwrap();
+        stream.write_value(7_u32).unwrap();
+        stream.seek(SeekFrom::Start(0)).unwrap();
+        stream.write_value(9_u32).unwrap();
+        stream.seek(SeekFrom::End(-4)).unwrap();
+        assert_eq!(stream.read_value::<u32>().unwrap(), 7);
+        assert_eq!(stream.drain(4..39_996).unwrap().len(), 39_992);
+        stream.extend_from_slice(&[1, 2]).unwrap();
+        stream.flush().unwrap();
+
+        let mut data = vec![];
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(data, vec![9, 0, 0, 0, 7, 0, 0, 0, 1, 2]);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<u32>().unwrap(), 9);
+    }
+    #[test]
+    fn test_buf_file_inner() {
+        let mut buf = BufFile::new(tempfile::tempfile().unwrap());
+        buf.write_all(b"abcd").unwrap();
+        assert_eq!(buf.length().unwrap(), 4);
+        let file = buf.get_mut().unwrap();
+        assert_eq!(file.metadata().unwrap().len(), 4);
+        assert_eq!(file.stream_position().unwrap(), 4);
+        file.write_all(b"ef").unwrap();
+        assert_eq!(buf.seek(SeekFrom::End(-1)).unwrap(), 5);
+        buf.seek(SeekFrom::Start(0)).unwrap();
+        buf.write_all(b"x").unwrap();
+
+        let mut file = buf.into_inner().unwrap();
+        assert_eq!(file.stream_position().unwrap(), 1);
+        let mut data = vec![];
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"xbcdef");
+    }
+}
@@ -1,5 +1,4 @@
 use crate::endian::Endian;
-use crate::pin::Pin;
 use crate::stream::{Data, Stream};
 use std::any::{Any, TypeId};
 use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
@@ -26,14 +25,41 @@ use std::{io, ops};
 pub trait ValueRead: Sized {
     fn read(stream: &mut Stream) -> io::Result<Self>;
 }
+/*
+读取时需要上下文参数(例如格式版本)的值
+ */
+pub trait ValueReadArgs<Args>: Sized {
+    fn read_args(stream: &mut Stream, args: Args) -> io::Result<Self>;
+}
+/*
+write_to 直接写入目标 stream(字节序取 stream.endian),不会为每个值分配新的 Stream
+write 保留旧的签名,默认写入新分配的 Stream 后返回
+ */
 #[allow(dead_code)]
 pub trait ValueWrite: Sized {
-    fn write(self, endian: &Endian) -> io::Result<Stream>;
+    fn write(self, endian: &Endian) -> io::Result<Stream> {
+        let mut stream = Stream::empty();
+        stream.with_endian(endian.clone());
+        self.write_to(&mut stream)?;
+        Ok(stream)
+    }
+    fn write_to(self, stream: &mut Stream) -> io::Result<()>;
 }
 pub trait Bytes {
     fn append(&mut self, data: &mut Stream) -> io::Result<u64>;
     // fn merge(&mut self, dest: Stream) -> io::Result<u64>;
     fn read_value<Value: ValueRead>(&mut self) -> io::Result<Value>;
+    fn read_value_args<Value: ValueReadArgs<Args>, Args>(
+        &mut self,
+        args: Args,
+    ) -> io::Result<Value>;
+    fn read_value_endian<Value: ValueRead>(&mut self, endian: Endian) -> io::Result<Value>;
+    fn read_value_be<Value: ValueRead>(&mut self) -> io::Result<Value>;
+    fn read_value_le<Value: ValueRead>(&mut self) -> io::Result<Value>;
+    fn read_value_at<Value: ValueRead>(&mut self, offset: u64) -> io::Result<Value>;
+    fn read_values<Value: 'static + ValueRead>(&mut self, count: u64) -> io::Result<Vec<Value>>;
+    fn read_values_size<Value: 'static + ValueRead>(&mut self, size: u64)
+    -> io::Result<Vec<Value>>;
     fn read_exact_size(&mut self, size: u64) -> io::Result<Vec<u8>>;
     fn fill_size(&mut self, size: u64) -> io::Result<&mut Self>;
     fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> io::Result<Vec<u8>>;
@@ -42,6 +68,13 @@ pub trait Bytes {
     fn insert_data(&mut self, data: &[u8]) -> io::Result<&mut Self>;
 }
 impl Stream {
+    /*
+    预分配的个数不超过剩余的字节数,避免损坏的数量前缀申请过大的内存
+     */
+    pub(crate) fn capacity_hint(&mut self, count: u64) -> usize {
+        let position = self.stream_position().unwrap_or(0);
+        count.min(self.length().saturating_sub(position)) as usize
+    }
     pub(crate) fn range_bounds<R: RangeBounds<usize>>(
         &mut self,
         range: R,
@@ -82,6 +115,11 @@ impl Stream {
 impl Bytes for Stream {
     fn append(&mut self, reader: &mut Stream) -> io::Result<u64> {
         let position = self.stream_position()?;
+        #[cfg(feature = "file")]
+        {
+            let remaining = reader.length().saturating_sub(reader.stream_position()?);
+            self.spill_check(position + remaining)?;
+        }
         let mut writer = self.data.borrow_mut();
         let bytes = writer.copy(reader.data.get_mut())?;
         if *self.length.borrow() == position {
@@ -112,15 +150,86 @@ impl Bytes for Stream {
     fn read_value<Value: ValueRead>(&mut self) -> io::Result<Value> {
         Value::read(self)
     }
+    fn read_value_args<Value: ValueReadArgs<Args>, Args>(
+        &mut self,
+        args: Args,
+    ) -> io::Result<Value> {
+        Value::read_args(self, args)
+    }
+    fn read_value_endian<Value: ValueRead>(&mut self, endian: Endian) -> io::Result<Value> {
+        self.with_scoped_endian(endian, |stream| Value::read(stream))
+    }
+    fn read_value_be<Value: ValueRead>(&mut self) -> io::Result<Value> {
+        self.read_value_endian(Endian::Big)
+    }
+    fn read_value_le<Value: ValueRead>(&mut self) -> io::Result<Value> {
+        self.read_value_endian(Endian::Little)
+    }
+    fn read_value_at<Value: ValueRead>(&mut self, offset: u64) -> io::Result<Value> {
+        self.read_at(offset, |stream| Value::read(stream))
+    }
+    fn read_values<Value: 'static + ValueRead>(&mut self, count: u64) -> io::Result<Vec<Value>> {
+        if TypeId::of::<Value>() == TypeId::of::<u8>() {
+            let data: Vec<u8> = self.read_exact_size(count)?;
+            let convert: Vec<Value> = unsafe { std::mem::transmute(data) };
+            return Ok(convert);
+        }
+        let mut values = Vec::with_capacity(self.capacity_hint(count));
+        for _ in 0..count {
+            values.push(self.read_value()?);
+        }
+        Ok(values)
+    }
+    /*
+    读取 size 个字节内的所有元素,最后一个元素越过边界时报错
+     */
+    fn read_values_size<Value: 'static + ValueRead>(
+        &mut self,
+        size: u64,
+    ) -> io::Result<Vec<Value>> {
+        if TypeId::of::<Value>() == TypeId::of::<u8>() {
+            let data: Vec<u8> = self.read_exact_size(size)?;
+            let convert: Vec<Value> = unsafe { std::mem::transmute(data) };
+            return Ok(convert);
+        }
+        let end = self
+            .stream_position()?
+            .checked_add(size)
+            .filter(|end| *end <= self.length())
+            .ok_or(Error::new(
+                ErrorKind::UnexpectedEof,
+                "source stream not enought data",
+            ))?;
+        let mut values = vec![];
+        while self.stream_position()? < end {
+            values.push(self.read_value()?);
+        }
+        if self.stream_position()? > end {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("values overrun byte length {}", size),
+            ));
+        }
+        Ok(values)
+    }
     fn read_exact_size(&mut self, size: u64) -> io::Result<Vec<u8>> {
-        let mut buf = vec![0_u8; size as usize];
-        self.data.borrow_mut().read_exact(&mut buf)?;
+        let mut buf = Vec::with_capacity(self.capacity_hint(size));
+        let mut data = self.data.borrow_mut();
+        (&mut *data).take(size).read_to_end(&mut buf)?;
+        if (buf.len() as u64) < size {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
         Ok(buf)
     }
     fn fill_size(&mut self, size: u64) -> io::Result<&mut Self> {
         let data_len = *self.length.borrow();
         if data_len < size {
             let diff = size - data_len;
+            #[cfg(feature = "file")]
+            self.spill_check(size)?;
             self.data
                 .borrow_mut()
                 .write_all(&vec![0_u8; diff as usize])?;
@@ -133,31 +242,24 @@ impl Bytes for Stream {
         let len = *self.length.borrow() as usize;
         let (start, end) = self.range_bounds(range)?;
         let drain_len = end - start;
-        self.pin()?;
-        // 读取要移除的数据
-        self.seek(SeekFrom::Start(start as u64))?;
-        let mut drained_data = vec![0; drain_len];
-        self.read_exact(&mut drained_data)?;
-        // 读取剩余数据
-        self.seek(SeekFrom::Start(end as u64))?;
-        let mut remaining_data = Vec::new();
-        self.read_to_end(&mut remaining_data)?;
-        // 将剩余数据写回到流中
-        self.seek(SeekFrom::Start(start as u64))?;
-        self.write_all(&remaining_data)?;
-
         let new_len = len - drain_len;
-
-        match &mut self.data.get_mut() {
-            #[cfg(feature = "file")]
-            Data::File { data, .. } => {
-                data.set_len(new_len as u64)?;
-            }
-            Data::Mem { data, .. } => {
-                data.get_mut().truncate(new_len);
-            }
-        }
-        self.un_pin()?;
+        /* 出错时 with_pin 也会恢复位置并移除 pin */
+        let drained_data = self.with_pin(|stream| {
+            // 读取要移除的数据
+            stream.seek(SeekFrom::Start(start as u64))?;
+            let mut drained_data = vec![0; drain_len];
+            stream.read_exact(&mut drained_data)?;
+            // 读取剩余数据
+            stream.seek(SeekFrom::Start(end as u64))?;
+            let mut remaining_data = Vec::new();
+            stream.read_to_end(&mut remaining_data)?;
+            // 先截断,不支持截断的后端在写入前返回错误
+            stream.data.get_mut().set_len(new_len as u64)?;
+            // 将剩余数据写回到流中
+            stream.seek(SeekFrom::Start(start as u64))?;
+            stream.write_all(&remaining_data)?;
+            Ok(drained_data)
+        })?;
         *self.length.borrow_mut() = new_len as u64;
         Ok(drained_data)
     }
@@ -165,6 +267,8 @@ impl Bytes for Stream {
     fn extend_from_slice(&mut self, data: &[u8]) -> io::Result<&mut Self> {
         // self.pin()?;
         let len = data.len();
+        #[cfg(feature = "file")]
+        self.spill_check(self.length() + len as u64)?;
         self.seek(SeekFrom::End(0))?;
         self.data.borrow_mut().write_all(data)?;
         // self.un_pin()?;
@@ -172,26 +276,52 @@ impl Bytes for Stream {
         Ok(self)
     }
     fn splice(&mut self, pos: u64, replace_with: Vec<u8>) -> io::Result<&mut Self> {
-        self.pin()?;
-        match &mut self.data.get_mut() {
-            #[cfg(feature = "file")]
-            Data::File { data: f, .. } => {
-                f.seek(SeekFrom::Start(pos))?;
-                // 读取插入点之后的数据
-                let mut remaining_data = Vec::new();
-                f.read_to_end(&mut remaining_data)?;
-                f.seek(SeekFrom::Start(pos))?;
-                f.write_all(&replace_with)?;
-                f.write_all(&remaining_data)?;
-                *self.length.borrow_mut() += replace_with.len() as u64;
-            }
-            Data::Mem { data, .. } => {
-                *self.length.borrow_mut() += replace_with.len() as u64;
-                data.get_mut()
-                    .splice(pos as usize..pos as usize, replace_with);
+        #[cfg(feature = "file")]
+        self.spill_check(self.length() + replace_with.len() as u64)?;
+        /* 出错时 with_pin 也会恢复位置并移除 pin */
+        self.with_pin(|stream| {
+            match &mut stream.data.get_mut() {
+                #[cfg(feature = "file")]
+                Data::File { data: f, .. } => {
+                    f.seek(SeekFrom::Start(pos))?;
+                    // 读取插入点之后的数据
+                    let mut remaining_data = Vec::new();
+                    f.read_to_end(&mut remaining_data)?;
+                    f.seek(SeekFrom::Start(pos))?;
+                    f.write_all(&replace_with)?;
+                    f.write_all(&remaining_data)?;
+                    *stream.length.borrow_mut() += replace_with.len() as u64;
+                }
+                Data::Mem { data, .. } => {
+                    *stream.length.borrow_mut() += replace_with.len() as u64;
+                    data.get_mut()
+                        .splice(pos as usize..pos as usize, replace_with);
+                }
+                #[cfg(feature = "mmap")]
+                Data::Mmap { .. } => {
+                    return Err(Error::new(
+                        ErrorKind::Unsupported,
+                        "mmap stream length can not be changed",
+                    ));
+                }
+                Data::Shared { .. } => {
+                    return Err(Error::new(
+                        ErrorKind::PermissionDenied,
+                        "shared stream is read only",
+                    ));
+                }
+                Data::Dyn { data, .. } => {
+                    data.seek(SeekFrom::Start(pos))?;
+                    let mut remaining_data = Vec::new();
+                    data.read_to_end(&mut remaining_data)?;
+                    data.seek(SeekFrom::Start(pos))?;
+                    data.write_all(&replace_with)?;
+                    data.write_all(&remaining_data)?;
+                    *stream.length.borrow_mut() += replace_with.len() as u64;
+                }
             }
-        }
-        self.un_pin()?;
+            Ok(())
+        })?;
         Ok(self)
     }
     fn insert_data(&mut self, data: &[u8]) -> io::Result<&mut Self> {
@@ -208,9 +338,64 @@ impl Bytes for Stream {
 }
 impl Stream {
     pub fn write_value<Value: ValueWrite>(&mut self, value: Value) -> io::Result<&mut Self> {
-        let mut data = value.write(&self.endian)?;
-        data.seek_start()?;
-        self.append(&mut data)?;
+        value.write_to(self)?;
+        Ok(self)
+    }
+    pub fn write_value_endian<Value: ValueWrite>(
+        &mut self,
+        value: Value,
+        endian: Endian,
+    ) -> io::Result<&mut Self> {
+        self.with_scoped_endian(endian, |stream| value.write_to(stream))?;
+        Ok(self)
+    }
+    pub fn write_value_be<Value: ValueWrite>(&mut self, value: Value) -> io::Result<&mut Self> {
+        self.write_value_endian(value, Endian::Big)
+    }
+    pub fn write_value_le<Value: ValueWrite>(&mut self, value: Value) -> io::Result<&mut Self> {
+        self.write_value_endian(value, Endian::Little)
+    }
+    /*
+    跳转到 offset 执行 f,无论成功失败都会通过 pin 恢复原来的位置
+     */
+    pub fn read_at<Value>(
+        &mut self,
+        offset: u64,
+        f: impl FnOnce(&mut Self) -> io::Result<Value>,
+    ) -> io::Result<Value> {
+        self.with_pin(|stream| {
+            stream.seek(SeekFrom::Start(offset))?;
+            f(stream)
+        })
+    }
+    /*
+    临时切换字节序执行 f,无论成功失败都会恢复原来的字节序
+     */
+    pub fn with_scoped_endian<Value>(
+        &mut self,
+        endian: Endian,
+        f: impl FnOnce(&mut Self) -> io::Result<Value>,
+    ) -> io::Result<Value> {
+        let endian = std::mem::replace(&mut self.endian, endian);
+        let value = f(self);
+        self.endian = endian;
+        value
+    }
+    /*
+    只写入元素,不带长度前缀
+     */
+    pub fn write_values<Value: 'static + ValueWrite>(
+        &mut self,
+        values: Vec<Value>,
+    ) -> io::Result<&mut Self> {
+        if TypeId::of::<Value>() == TypeId::of::<u8>() {
+            let data: Vec<u8> = unsafe { std::mem::transmute(values) };
+            self.write_all(&data)?;
+            return Ok(self);
+        }
+        for value in values {
+            self.write_value(value)?;
+        }
         Ok(self)
     }
 }
@@ -233,26 +418,65 @@ macro_rules! value_read {
         )*
     }
 }
-value_read!(u8, 1, i8, 1, u16, 2, i16, 2, u32, 4, i32, 4, u64, 8, i64, 8);
+value_read!(
+    u8, 1, i8, 1, u16, 2, i16, 2, u32, 4, i32, 4, u64, 8, i64, 8, u128, 16, i128, 16, f32, 4, f64,
+    8
+);
 
 #[macro_export]
 macro_rules! value_write {
     ($($typ:ty),*) => {
         $(
             impl ValueWrite for $typ {
-                fn write(self, endian: &crate::endian::Endian) -> std::io::Result<Stream> {
+                fn write_to(self, stream: &mut Stream) -> std::io::Result<()> {
                     use crate::endian::Endian;
-                    let value = match endian {
-                        Endian::Big => self.to_be_bytes().to_vec(),
-                        Endian::Little => self.to_le_bytes().to_vec(),
+                    let value = match stream.endian {
+                        Endian::Big => self.to_be_bytes(),
+                        Endian::Little => self.to_le_bytes(),
                     };
-                    Ok(value.into())
+                    stream.write_all(&value)
                 }
             }
         )*
     }
 }
-value_write!(u8, i8, u16, i16, u32, i32, u64, i64);
+value_write!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
+
+/*
+usize/isize 固定按 64 位读写,32 位与 64 位平台生成的数据一致
+ */
+impl ValueRead for usize {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let value: u64 = stream.read_value()?;
+        usize::try_from(value).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("value {} overflow usize", value),
+            )
+        })
+    }
+}
+impl ValueWrite for usize {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (self as u64).write_to(stream)
+    }
+}
+impl ValueRead for isize {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let value: i64 = stream.read_value()?;
+        isize::try_from(value).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("value {} overflow isize", value),
+            )
+        })
+    }
+}
+impl ValueWrite for isize {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (self as i64).write_to(stream)
+    }
+}
 
 pub trait FromBytes<const N: usize> {
     fn from_be_bytes(data: [u8; N]) -> Self;
@@ -262,12 +486,9 @@ pub trait FromBytes<const N: usize> {
 macro_rules! enum_to_bytes {
     ($typ:ty,$btyp:ty) => {
         impl fast_stream::bytes::ValueWrite for $typ {
-            fn write(
-                self,
-                endian: &fast_stream::endian::Endian,
-            ) -> std::io::Result<fast_stream::stream::Stream> {
+            fn write_to(self, stream: &mut fast_stream::stream::Stream) -> std::io::Result<()> {
                 let value: $btyp = self.clone().into();
-                value.write(endian)
+                value.write_to(stream)
             }
         }
         impl fast_stream::bytes::ValueRead for $typ {
@@ -276,21 +497,21 @@ macro_rules! enum_to_bytes {
             ) -> std::io::Result<Self> {
                 use fast_stream::bytes::Bytes;
                 let value: $btyp = stream.read_value()?;
-                Ok(value.into())
+                <$typ>::try_from(value)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
             }
         }
     };
 }
 impl ValueWrite for String {
-    fn write(self, _endian: &Endian) -> io::Result<Stream> {
-        let mut data = self.as_bytes().to_vec();
-        data.push(0_u8);
-        Ok(data.into())
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        stream.write_all(self.as_bytes())?;
+        stream.write_all(&[0_u8])
     }
 }
 impl ValueWrite for bool {
-    fn write(self, endian: &Endian) -> io::Result<Stream> {
-        u8::from(self).write(endian)
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        u8::from(self).write_to(stream)
     }
 }
 impl ValueRead for bool {
@@ -299,13 +520,79 @@ impl ValueRead for bool {
         Ok(value == 1)
     }
 }
-impl ValueRead for [u8; 4] {
+/*
+[u8; N] 整块读写,其它类型逐个元素读写,都在栈上完成,不经过 Vec
+ */
+impl<TYPE: 'static + ValueRead, const N: usize> ValueRead for [TYPE; N] {
     fn read(stream: &mut Stream) -> io::Result<Self> {
-        let mut value = [0u8; 4];
-        stream.read_exact(&mut value)?;
-        Ok(value)
+        if TypeId::of::<TYPE>() == TypeId::of::<u8>() {
+            let mut bytes = [0_u8; N];
+            stream.read_exact(&mut bytes)?;
+            if let Some(values) = (&mut Some(bytes) as &mut dyn Any)
+                .downcast_mut::<Option<Self>>()
+                .and_then(Option::take)
+            {
+                return Ok(values);
+            }
+        }
+        let mut error = None;
+        let values: [Option<TYPE>; N] = std::array::from_fn(|_| {
+            if error.is_some() {
+                return None;
+            }
+            stream.read_value().map_err(|e| error = Some(e)).ok()
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        /* 没有错误时每个元素都已读取 */
+        Ok(values.map(Option::unwrap))
     }
 }
+impl<TYPE: 'static + ValueWrite, const N: usize> ValueWrite for [TYPE; N] {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        if let Some(bytes) = (&self as &dyn Any).downcast_ref::<[u8; N]>() {
+            return stream.write_all(bytes);
+        }
+        for value in self {
+            value.write_to(stream)?;
+        }
+        Ok(())
+    }
+}
+macro_rules! value_tuple {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: ValueRead),+> ValueRead for ($($name,)+) {
+                fn read(stream: &mut Stream) -> io::Result<Self> {
+                    Ok(($(stream.read_value::<$name>()?,)+))
+                }
+            }
+            impl<$($name: ValueWrite),+> ValueWrite for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+                    let ($($name,)+) = self;
+                    $($name.write_to(stream)?;)+
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+value_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+    (A, B, C, D, E, F, G, H, I),
+    (A, B, C, D, E, F, G, H, I, J),
+    (A, B, C, D, E, F, G, H, I, J, K),
+    (A, B, C, D, E, F, G, H, I, J, K, L)
+);
 impl<TYPE: 'static + ValueRead> ValueRead for Vec<TYPE> {
     fn read(stream: &mut Stream) -> io::Result<Self> {
         let len: u64 = stream.read_value()?;
@@ -332,31 +619,19 @@ impl<TYPE: 'static + ValueRead> ValueRead for Option<TYPE> {
     }
 }
 impl<TYPE: 'static + ValueWrite> ValueWrite for Option<TYPE> {
-    fn write(self, endian: &Endian) -> io::Result<Stream> {
-        let mut stream = Stream::empty();
-        stream.with_endian(endian.clone());
-        stream.write_value(self.is_some())?;
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        self.is_some().write_to(stream)?;
         if let Some(value) = self {
-            stream.write_value(value)?;
+            value.write_to(stream)?;
         }
-        Ok(stream)
+        Ok(())
     }
 }
 impl<TYPE: 'static + ValueWrite> ValueWrite for Vec<TYPE> {
-    fn write(self, endian: &Endian) -> io::Result<Stream> {
-        let mut stream = Stream::empty();
-        stream.with_endian(endian.clone());
-        let len = self.len() as u64;
-        stream.write_value(len)?;
-        if TypeId::of::<TYPE>() == TypeId::of::<u8>() {
-            let data: Vec<u8> = unsafe { std::mem::transmute(self) };
-            stream.write(&data)?;
-            return Ok(stream);
-        }
-        for item in self {
-            stream.write_value(item)?;
-        }
-        Ok(stream)
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (self.len() as u64).write_to(stream)?;
+        stream.write_values(self)?;
+        Ok(())
     }
 }
 // impl<TYPE: 'static + ValueWrite> ValueWrite for Vec<TYPE> {
@@ -368,14 +643,7 @@ impl<TYPE: 'static + ValueWrite> ValueWrite for Vec<TYPE> {
 // }
 impl ValueRead for String {
     fn read(stream: &mut Stream) -> io::Result<Self> {
-        let mut bytes = vec![];
-        loop {
-            let byte: u8 = stream.read_value()?;
-            if byte == 0 {
-                break;
-            }
-            bytes.push(byte);
-        }
+        let bytes = stream.read_until_nul()?;
         String::from_utf8(bytes)
             .map_err(|e| Error::new(ErrorKind::InvalidData, format!("bytes to string {}", e)))
     }
@@ -383,6 +651,8 @@ impl ValueRead for String {
 #[cfg(test)]
 mod test {
     use crate::bytes::Bytes;
+    use crate::endian::Endian;
+    use crate::pin::Pin;
     use crate::stream::Stream;
     use std::fs::OpenOptions;
     use std::io::{Read, Seek, SeekFrom};
@@ -406,10 +676,427 @@ mod test {
     fn test_vec_splice() {
         let mut stream = Stream::empty();
         stream.splice(0, vec![1, 2, 3]).unwrap();
-        assert_eq!(stream.length, 3);
+        assert_eq!(stream.length(), 3);
         assert_eq!(stream.take_data().unwrap(), vec![1, 2, 3]);
         // let mut data = vec![1, 2, 3];
         // data.splice(0..1, vec![0, 0, 0, 0]);
         // assert_eq!(data, vec![1, 0, 0])
     }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_struct() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Header {
+            magic: u32,
+            version: u16,
+            name: String,
+            items: Vec<u8>,
+        }
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Pair(u8, Option<u32>);
+
+        let header = Header {
+            magic: 0xFEEDFACF,
+            version: 2,
+            name: "fast".to_string(),
+            items: vec![1, 2, 3],
+        };
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        stream.write_value(header.clone()).unwrap();
+        stream.write_value(Pair(7, Some(9))).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(
+            stream.read_exact_size(4).unwrap(),
+            vec![0xFE, 0xED, 0xFA, 0xCF]
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<Header>().unwrap(), header);
+        assert_eq!(stream.read_value::<Pair>().unwrap(), Pair(7, Some(9)));
+
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Gen<T> {
+            first: T,
+            rest: Vec<T>,
+        }
+        let value = Gen {
+            first: 1_u16,
+            rest: vec![2, 3],
+        };
+        stream.clear().unwrap();
+        stream.write_value(value.clone()).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<Gen<u16>>().unwrap(), value);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_enum() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[repr(u16)]
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        enum Command {
+            Segment { name: String, size: u64 } = 1,
+            Symtab(u32, u32) = 5,
+            Unit,
+        }
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        let commands = vec![
+            Command::Symtab(1, 2),
+            Command::Segment {
+                name: "__TEXT".to_string(),
+                size: 4096,
+            },
+            Command::Unit,
+        ];
+        for command in commands.clone() {
+            stream.write_value(command).unwrap();
+        }
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_exact_size(2).unwrap(), vec![0, 5]);
+        stream.seek_start().unwrap();
+        for command in commands {
+            assert_eq!(stream.read_value::<Command>().unwrap(), command);
+        }
+        stream.seek_start().unwrap();
+        stream.write_value(9_u16).unwrap();
+        stream.seek_start().unwrap();
+        let error = stream.read_value::<Command>().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_count() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Table {
+            n_entries: u16,
+            size: u32,
+            #[stream(count = "n_entries")]
+            entries: Vec<u32>,
+            #[stream(byte_len = "size")]
+            names: Vec<String>,
+            #[stream(count = "n_entries - 1")]
+            bytes: Vec<u8>,
+        }
+        let table = Table {
+            n_entries: 2,
+            size: 5,
+            entries: vec![1, 2],
+            names: vec!["ab".to_string(), "c".to_string()],
+            bytes: vec![7],
+        };
+        let mut stream = Stream::empty();
+        stream.write_value(table.clone()).unwrap();
+        assert_eq!(stream.length(), 2 + 4 + 8 + 5 + 1);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<Table>().unwrap(), table);
+
+        let mut stream: Stream = vec![1, 0, 2, 0, 0, 0, 5, 0, 0, 0, b'a', b'b', 0, 9].into();
+        let error = stream.read_value::<Table>().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut stream: Stream = vec![0xFF, 0xFF, 0, 0, 0, 0, 1, 0, 0, 0].into();
+        let error = stream.read_value::<Table>().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        stream.seek_start().unwrap();
+        let error = stream.read_values::<u32>(u64::MAX).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        let error = stream.read_exact_size(u64::MAX).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        stream.set_position(1).unwrap();
+        let error = stream.read_values_size::<u32>(u64::MAX).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_magic() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        #[stream(magic = b"PK\x03\x04")]
+        struct LocalFile {
+            #[stream(assert = "version <= 63")]
+            version: u16,
+            #[stream(assert = "flags & 1 == 0 && version > 10")]
+            flags: u16,
+        }
+        let file = LocalFile {
+            version: 20,
+            flags: 8,
+        };
+        let mut stream = Stream::empty();
+        stream.write_value(file.clone()).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<LocalFile>().unwrap(), file);
+
+        let mut stream: Stream = vec![0x50, 0x4B, 0x03, 0x04, 64, 0, 0, 0].into();
+        let error = stream.read_value::<LocalFile>().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "LocalFile.version: assert `version <= 63` failed at offset 4"
+        );
+        let mut stream: Stream = vec![0x50, 0x4B, 0x05, 0x06, 20, 0, 0, 0].into();
+        let error = stream.read_value::<LocalFile>().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("LocalFile: magic mismatch at offset 0")
+        );
+    }
+    #[test]
+    fn test_value_endian() {
+        let mut stream = Stream::empty();
+        stream.write_value_be(1_u16).unwrap();
+        stream.write_value_le(1_u16).unwrap();
+        stream.write_value(1_u16).unwrap();
+        assert_eq!(stream.copy_data().unwrap(), vec![0, 1, 1, 0, 1, 0]);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value_be::<u16>().unwrap(), 1);
+        assert_eq!(stream.read_value_le::<u16>().unwrap(), 1);
+        assert!(stream.read_value_be::<u32>().is_err());
+        assert_eq!(stream.endian, Endian::Little);
+    }
+    #[test]
+    fn test_wide_values() {
+        let mut stream = Stream::empty();
+        stream
+            .write_value(1.5_f32)
+            .unwrap()
+            .write_value(-2.25_f64)
+            .unwrap()
+            .write_value(u128::MAX - 1)
+            .unwrap()
+            .write_value(i128::MIN)
+            .unwrap()
+            .write_value(7_usize)
+            .unwrap()
+            .write_value(-7_isize)
+            .unwrap();
+        assert_eq!(stream.length(), 4 + 8 + 16 + 16 + 8 + 8);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<f32>().unwrap(), 1.5);
+        assert_eq!(stream.read_value::<f64>().unwrap(), -2.25);
+        assert_eq!(stream.read_value::<u128>().unwrap(), u128::MAX - 1);
+        assert_eq!(stream.read_value::<i128>().unwrap(), i128::MIN);
+        assert_eq!(stream.read_value::<usize>().unwrap(), 7);
+        assert_eq!(stream.read_value::<isize>().unwrap(), -7);
+    }
+    #[test]
+    fn test_array_tuple() {
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        stream
+            .write_value([1_u8, 2, 3])
+            .unwrap()
+            .write_value([4_u16, 5])
+            .unwrap()
+            .write_value((6_u16, 7_u8, [8_u32; 2]))
+            .unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![1, 2, 3, 0, 4, 0, 5, 0, 6, 7, 0, 0, 0, 8, 0, 0, 0, 8]
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<[u8; 3]>().unwrap(), [1, 2, 3]);
+        assert_eq!(stream.read_value::<[u16; 2]>().unwrap(), [4, 5]);
+        assert_eq!(
+            stream.read_value::<(u16, u8, [u32; 2])>().unwrap(),
+            (6, 7, [8, 8])
+        );
+        assert!(stream.read_value::<[u8; 1]>().is_err());
+        assert!(stream.read_value::<[u16; 1]>().is_err());
+    }
+    #[test]
+    fn test_write_compat() {
+        use crate::bytes::ValueWrite;
+        use std::io;
+
+        /* 旧代码覆盖的 write 仍然可以编译,write_to 经由 write 再 append */
+        struct Legacy(u16);
+        impl ValueWrite for Legacy {
+            fn write(self, endian: &Endian) -> io::Result<Stream> {
+                self.0.write(endian)
+            }
+            fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+                let mut data = self.write(&stream.endian)?;
+                data.seek_start()?;
+                stream.append(&mut data)?;
+                Ok(())
+            }
+        }
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        stream
+            .write_value(1_u8)
+            .unwrap()
+            .write_value(vec![Legacy(2)])
+            .unwrap()
+            .write_value_le(Legacy(3))
+            .unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 3, 0]
+        );
+        assert_eq!(stream.endian, Endian::Big);
+        assert_eq!(Legacy(4).write(&Endian::Little).unwrap().length(), 2);
+    }
+    #[test]
+    fn test_read_value_at() {
+        let mut stream: Stream = vec![1, 2, 0, 0, 0].into();
+        stream.set_position(1).unwrap();
+        assert_eq!(stream.read_value_at::<u32>(1).unwrap(), 2);
+        assert!(stream.read_value_at::<u32>(3).is_err());
+        assert_eq!(stream.position().unwrap(), 1);
+        assert!(stream.un_pin().is_err());
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_at() {
+        use crate::derive::ValueRead;
+        #[derive(Debug, Clone, PartialEq, ValueRead)]
+        struct Symbol {
+            name_offset: u8,
+            #[stream(at = "name_offset", relative_to = "start")]
+            name: String,
+            value: u8,
+            #[stream(at = "self.value", little)]
+            other: u16,
+        }
+        #[derive(Debug, Clone, PartialEq, ValueRead)]
+        #[stream(args = "base: u64")]
+        struct Wrapper {
+            #[stream(at = "1", relative_to = "current")]
+            first: u8,
+            #[stream(at = "0", relative_to = "base")]
+            second: u8,
+            tail: u8,
+        }
+        let mut stream: Stream = vec![9, 3, 6, 0, b'a', 0, 5, 0].into();
+        stream.set_position(1).unwrap();
+        let symbol: Symbol = stream.read_value().unwrap();
+        assert_eq!(symbol.name, "a");
+        assert_eq!(symbol.other, 5);
+        assert_eq!(stream.position().unwrap(), 3);
+        stream.set_position(0).unwrap();
+        let wrapper: Wrapper = stream.read_value_args(2).unwrap();
+        assert_eq!(
+            wrapper,
+            Wrapper {
+                first: 3,
+                second: 6,
+                tail: 9
+            }
+        );
+        stream.set_position(6).unwrap();
+        assert!(stream.read_value::<Symbol>().is_err());
+        assert_eq!(stream.position().unwrap(), 7);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_bit_fields() {
+        use crate::derive::BitFields;
+        #[derive(Debug, Clone, Copy, PartialEq, BitFields)]
+        #[bits(kind: u8 = 0..3, enabled: bool = 3)]
+        #[bits(level: u16 = 4..=12, top: u8 = 31)]
+        struct Flags(u32);
+
+        let mut stream: Stream = vec![0x00, 0x00, 0x12, 0x8D].into();
+        stream.with_big_endian();
+        let mut flags: Flags = stream.read_value().unwrap();
+        assert_eq!(flags.kind(), 5);
+        assert!(flags.enabled());
+        assert_eq!(flags.level(), 0x128);
+        assert_eq!(flags.top(), 0);
+        flags
+            .set_enabled(false)
+            .set_kind(2)
+            .set_top(1)
+            .set_level(0x1FFF);
+        assert_eq!(flags.level(), 0x1FF);
+        assert_eq!(flags.0, 0x8000_1FF2);
+        stream.seek_start().unwrap();
+        stream.write_value(flags).unwrap();
+        assert_eq!(stream.copy_data().unwrap(), vec![0x80, 0x00, 0x1F, 0xF2]);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_endian() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct FatArch {
+            #[stream(big)]
+            cpu_type: u32,
+            #[stream(little)]
+            offsets: Vec<u16>,
+            align: u16,
+        }
+        let arch = FatArch {
+            cpu_type: 7,
+            offsets: vec![1],
+            align: 2,
+        };
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        stream.write_value(arch.clone()).unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![0, 0, 0, 7, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 2]
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<FatArch>().unwrap(), arch);
+        assert_eq!(stream.endian, Endian::Big);
+    }
+    #[cfg(feature = "enum")]
+    #[test]
+    fn test_derive_condition() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        #[stream(args = "version: u16")]
+        struct Entry {
+            flags: u8,
+            #[stream(if = "self.flags & 1 != 0")]
+            extra: Option<u32>,
+            #[stream(if = "version >= 2")]
+            checksum: Option<u16>,
+        }
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct File {
+            version: u16,
+            #[stream(args = "version")]
+            first: Entry,
+            #[stream(args = "self.version")]
+            second: Entry,
+        }
+        let file = File {
+            version: 2,
+            first: Entry {
+                flags: 1,
+                extra: Some(7),
+                checksum: Some(3),
+            },
+            second: Entry {
+                flags: 0,
+                extra: None,
+                checksum: Some(4),
+            },
+        };
+        let mut stream = Stream::empty();
+        stream.write_value(file.clone()).unwrap();
+        assert_eq!(stream.length(), 2 + 7 + 3);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<File>().unwrap(), file);
+
+        let mut stream: Stream = vec![1, 7, 0, 0, 0].into();
+        let entry: Entry = stream.read_value_args(1).unwrap();
+        assert_eq!(
+            entry,
+            Entry {
+                flags: 1,
+                extra: Some(7),
+                checksum: None
+            }
+        );
+    }
 }
@@ -0,0 +1,203 @@
+use crate::bytes::{Bytes, ValueRead, ValueWrite};
+use crate::endian::Endian;
+use crate::stream::Stream;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/*
+与 Vec<TYPE> 相同:u64 个数前缀,之后依次是元素,map 的元素是 key 与 value
+hash 类型的遍历顺序不固定,按 key 编码后的字节排序,保证相同内容写出相同的数据
+ */
+fn write_sorted(stream: &mut Stream, mut entries: Vec<(Vec<u8>, Vec<u8>)>) -> io::Result<()> {
+    entries.sort_unstable();
+    (entries.len() as u64).write_to(stream)?;
+    for (key, value) in entries {
+        stream.write_all(&key)?;
+        stream.write_all(&value)?;
+    }
+    Ok(())
+}
+
+fn encode<V: ValueWrite>(value: V, endian: &Endian) -> io::Result<Vec<u8>> {
+    value.write(endian)?.take_data()
+}
+
+impl<K: ValueRead + Eq + Hash, V: ValueRead> ValueRead for HashMap<K, V> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len: u64 = stream.read_value()?;
+        let mut values = HashMap::with_capacity(stream.capacity_hint(len));
+        for _ in 0..len {
+            let key = stream.read_value()?;
+            values.insert(key, stream.read_value()?);
+        }
+        Ok(values)
+    }
+}
+impl<K: ValueWrite, V: ValueWrite> ValueWrite for HashMap<K, V> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        let endian = &stream.endian;
+        let entries = self
+            .into_iter()
+            .map(|(key, value)| Ok((encode(key, endian)?, encode(value, endian)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+        write_sorted(stream, entries)
+    }
+}
+impl<K: ValueRead + Eq + Hash> ValueRead for HashSet<K> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len: u64 = stream.read_value()?;
+        let mut values = HashSet::with_capacity(stream.capacity_hint(len));
+        for _ in 0..len {
+            values.insert(stream.read_value()?);
+        }
+        Ok(values)
+    }
+}
+impl<K: ValueWrite> ValueWrite for HashSet<K> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        let endian = &stream.endian;
+        let entries = self
+            .into_iter()
+            .map(|key| Ok((encode(key, endian)?, vec![])))
+            .collect::<io::Result<Vec<_>>>()?;
+        write_sorted(stream, entries)
+    }
+}
+impl<K: ValueRead + Ord, V: ValueRead> ValueRead for BTreeMap<K, V> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len: u64 = stream.read_value()?;
+        let mut values = BTreeMap::new();
+        for _ in 0..len {
+            let key = stream.read_value()?;
+            values.insert(key, stream.read_value()?);
+        }
+        Ok(values)
+    }
+}
+impl<K: ValueWrite, V: ValueWrite> ValueWrite for BTreeMap<K, V> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (self.len() as u64).write_to(stream)?;
+        for (key, value) in self {
+            key.write_to(stream)?;
+            value.write_to(stream)?;
+        }
+        Ok(())
+    }
+}
+impl<K: ValueRead + Ord> ValueRead for BTreeSet<K> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len: u64 = stream.read_value()?;
+        let mut values = BTreeSet::new();
+        for _ in 0..len {
+            values.insert(stream.read_value()?);
+        }
+        Ok(values)
+    }
+}
+impl<K: ValueWrite> ValueWrite for BTreeSet<K> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (self.len() as u64).write_to(stream)?;
+        for key in self {
+            key.write_to(stream)?;
+        }
+        Ok(())
+    }
+}
+impl<TYPE: 'static + ValueRead> ValueRead for VecDeque<TYPE> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        Ok(Vec::<TYPE>::read(stream)?.into())
+    }
+}
+impl<TYPE: 'static + ValueWrite> ValueWrite for VecDeque<TYPE> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        Vec::from(self).write_to(stream)
+    }
+}
+impl<TYPE: ValueRead> ValueRead for Box<TYPE> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        Ok(Box::new(stream.read_value()?))
+    }
+}
+impl<TYPE: ValueWrite> ValueWrite for Box<TYPE> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        (*self).write_to(stream)
+    }
+}
+impl<TYPE: ValueRead> ValueRead for Rc<TYPE> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        Ok(Rc::new(stream.read_value()?))
+    }
+}
+/*
+还有其他引用时 clone 出内容再写入
+ */
+impl<TYPE: ValueWrite + Clone> ValueWrite for Rc<TYPE> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        Rc::unwrap_or_clone(self).write_to(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::stream::Stream;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+    use std::io::ErrorKind;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_collections() {
+        let map: HashMap<u16, String> = (0..32).map(|i| (i, i.to_string())).collect();
+        let mut first = Stream::empty();
+        first.write_value(map.clone()).unwrap();
+        let mut entries: Vec<_> = map.clone().into_iter().collect();
+        entries.reverse();
+        let mut second = Stream::empty();
+        second
+            .write_value(entries.into_iter().collect::<HashMap<_, _>>())
+            .unwrap();
+        assert_eq!(first.copy_data().unwrap(), second.copy_data().unwrap());
+        first.seek_start().unwrap();
+        assert_eq!(first.read_value::<HashMap<u16, String>>().unwrap(), map);
+
+        let set: HashSet<u32> = [3, 1, 2].into();
+        let tree: BTreeMap<u8, u8> = [(2, 1), (1, 2)].into();
+        let tree_set: BTreeSet<u8> = [5, 4].into();
+        let deque: VecDeque<u8> = [9, 8].into();
+        let mut stream = Stream::empty();
+        stream
+            .write_value(set.clone())
+            .unwrap()
+            .write_value(tree.clone())
+            .unwrap()
+            .write_value(tree_set.clone())
+            .unwrap()
+            .write_value(deque.clone())
+            .unwrap()
+            .write_value(Box::new(7_u8))
+            .unwrap()
+            .write_value(Rc::new(6_u8))
+            .unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<HashSet<u32>>().unwrap(), set);
+        assert_eq!(stream.read_value::<BTreeMap<u8, u8>>().unwrap(), tree);
+        assert_eq!(stream.read_value::<BTreeSet<u8>>().unwrap(), tree_set);
+        assert_eq!(stream.read_value::<VecDeque<u8>>().unwrap(), deque);
+        assert_eq!(*stream.read_value::<Box<u8>>().unwrap(), 7);
+        assert_eq!(*stream.read_value::<Rc<u8>>().unwrap(), 6);
+
+        let mut stream: Stream = vec![0xFF; 8].into();
+        assert_eq!(
+            stream.read_value::<HashMap<u8, u8>>().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(
+            stream.read_value::<HashSet<u8>>().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -39,6 +39,8 @@ impl Deflate for Stream {
             vec![]
         };
         let length = compress_data.len() as u64;
+        #[cfg(feature = "file")]
+        self.spill_check(length)?;
         self.data.borrow_mut().write_all(&compress_data)?;
         *self.length.borrow_mut() = length;
         *self.pins.borrow_mut() = vec![];
@@ -81,6 +83,8 @@ impl Deflate for Stream {
         };
         let length = compress_data.len() as u64;
         self.data.borrow_mut().clear()?;
+        #[cfg(feature = "file")]
+        self.spill_check(length)?;
         self.data.borrow_mut().write_all(&compress_data)?;
         *self.length.borrow_mut() = length;
         *self.pins.borrow_mut() = vec![];
@@ -91,9 +95,11 @@ impl Deflate for Stream {
         let data = self.copy_data()?;
         let un_compress_data = decompress_to_vec(&data)
             .map_err(|_e| Error::new(ErrorKind::InvalidData, std::fmt::Error::default()))?;
+        let length = un_compress_data.len() as u64;
         self.data.borrow_mut().clear()?;
+        #[cfg(feature = "file")]
+        self.spill_check(length)?;
         self.data.borrow_mut().write_all(&un_compress_data)?;
-        let length = un_compress_data.len() as u64;
         *self.length.borrow_mut() = length;
         *self.pins.borrow_mut() = vec![];
         Ok(length)
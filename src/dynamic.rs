@@ -0,0 +1,200 @@
+use crate::stream::Data;
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+
+/*
+任意实现了 Read + Write + Seek 的类型都可以作为 Data::Dyn 的后端,只读的类型用 ReadOnly 包装
+要求 Send,保证 Stream 仍然可以跨线程移动;as_any_mut/into_any 用于取回原来的类型
+ */
+pub trait ReadWriteSeek: Read + Write + Seek + Send {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send>;
+}
+impl<T: Read + Write + Seek + Send + 'static> ReadWriteSeek for T {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send> {
+        self
+    }
+}
+impl Debug for dyn ReadWriteSeek + '_ {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("dyn ReadWriteSeek")
+    }
+}
+
+/*
+只读包装,写入返回 PermissionDenied
+ */
+#[derive(Debug)]
+pub struct ReadOnly<T>(pub T);
+impl<T: Read> Read for ReadOnly<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+impl<T> Write for ReadOnly<T> {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "dyn stream is read only",
+        ))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl<T: Seek> Seek for ReadOnly<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+/*
+只能在结尾补 0 变长,不能截断
+ */
+pub(crate) fn set_len(data: &mut dyn ReadWriteSeek, len: u64) -> io::Result<()> {
+    let position = data.stream_position()?;
+    let end = data.seek(SeekFrom::End(0))?;
+    if len < end {
+        data.seek(SeekFrom::Start(position))?;
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "dyn stream can not be truncated",
+        ));
+    }
+    io::copy(&mut io::repeat(0).take(len - end), data)?;
+    data.seek(SeekFrom::Start(position))?;
+    Ok(())
+}
+pub(crate) fn length(data: &mut dyn ReadWriteSeek) -> io::Result<u64> {
+    let position = data.stream_position()?;
+    let length = data.seek(SeekFrom::End(0))?;
+    data.seek(SeekFrom::Start(position))?;
+    Ok(length)
+}
+
+impl Data {
+    /*
+    后端需要能 seek 到结尾取得长度,否则返回 seek 的错误
+     */
+    pub fn dynamic(data: impl ReadWriteSeek + 'static) -> io::Result<Self> {
+        let mut data: Box<dyn ReadWriteSeek> = Box::new(data);
+        length(data.as_mut())?;
+        Ok(Data::Dyn {
+            crc32: None,
+            sha1: None,
+            sha2: None,
+            data,
+        })
+    }
+    /*
+    Dyn 后端是 T 时返回它的可变引用
+     */
+    pub fn dyn_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        match self {
+            Data::Dyn { data, .. } => data.as_mut().as_any_mut().downcast_mut(),
+            _ => None,
+        }
+    }
+    /*
+    Dyn 后端是 T 时取回 T,否则原样返回
+     */
+    #[allow(clippy::result_large_err)]
+    pub fn into_dyn<T: 'static>(mut self) -> Result<T, Self> {
+        if self.dyn_mut::<T>().is_none() {
+            return Err(self);
+        }
+        match self {
+            Data::Dyn { data, .. } => Ok(*data.into_any().downcast().unwrap()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::dynamic::ReadOnly;
+    use crate::stream::{Data, Stream};
+    use std::io;
+    use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_dynamic() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Stream>();
+        let mut stream = Stream::new(Data::dynamic(Cursor::new(vec![])).unwrap());
+        stream.init_crc32();
+        stream
+            .write_value(1_u16)
+            .unwrap()
+            .write_value("ab".to_string())
+            .unwrap();
+        assert_eq!(stream.crc32_value(), crc32fast::hash(b"\x01\x00ab\0"));
+        stream.align(4).unwrap();
+        assert_eq!(stream.length(), 8);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<u16>().unwrap(), 1);
+        assert_eq!(stream.read_value::<String>().unwrap(), "ab");
+        assert_eq!(
+            stream.drain(0..1).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(
+            stream.take_data().unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(stream.length(), 8);
+        stream.seek_start().unwrap();
+        assert_eq!(stream.copy_data().unwrap(), b"\x01\x00ab\0\0\0\0");
+        /* 写入的数据在调用方提供的 writer 中 */
+        let data = stream.data.get_mut();
+        assert_eq!(
+            data.dyn_mut::<Cursor<Vec<u8>>>().unwrap().get_ref().len(),
+            8
+        );
+        let data = stream.data.into_inner();
+        let data = data.into_dyn::<Vec<u8>>().unwrap_err();
+        let writer = data.into_dyn::<Cursor<Vec<u8>>>().unwrap();
+        assert_eq!(writer.into_inner(), b"\x01\x00ab\0\0\0\0");
+
+        let mut stream = Stream::new(Data::dynamic(ReadOnly(Cursor::new(b"\x02\0cd\0"))).unwrap());
+        assert_eq!(stream.length(), 5);
+        assert_eq!(
+            stream.write_value(1_u8).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+    }
+    #[test]
+    fn test_dynamic_unseekable() {
+        struct Unseekable(Cursor<Vec<u8>>);
+        impl Read for Unseekable {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+        impl Write for Unseekable {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        impl Seek for Unseekable {
+            fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+                Err(io::Error::new(ErrorKind::Unsupported, "not seekable"))
+            }
+        }
+        assert_eq!(
+            Data::dynamic(Unseekable(Cursor::new(vec![])))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unsupported
+        );
+    }
+}
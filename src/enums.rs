@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::ErrorKind;
+
+/*
+NumToEnum 生成的 TryFrom 在数值无法匹配任何 variant 时返回
+ */
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownValue {
+    pub name: &'static str,
+    pub value: i128,
+}
+impl Display for UnknownValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} can not match {}", self.name, self.value)
+    }
+}
+impl std::error::Error for UnknownValue {}
+impl From<UnknownValue> for io::Error {
+    fn from(value: UnknownValue) -> Self {
+        io::Error::new(ErrorKind::InvalidData, value)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::derive::NumToEnum;
+    use crate::enum_to_bytes;
+    use crate::enums::UnknownValue;
+    use crate::stream::Stream;
+    use std::io::ErrorKind;
+
+    #[repr(u8)]
+    #[derive(Debug, Clone, PartialEq, NumToEnum)]
+    enum Cpu {
+        X84 = 1,
+        Arm = 2,
+    }
+    enum_to_bytes!(Cpu, u8);
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(Cpu::try_from(2), Ok(Cpu::Arm));
+        assert_eq!(
+            Cpu::try_from(3),
+            Err(UnknownValue {
+                name: "Cpu",
+                value: 3
+            })
+        );
+        let mut stream = Stream::new(vec![1, 9].into());
+        assert_eq!(stream.read_value::<Cpu>().unwrap(), Cpu::X84);
+        let error = stream.read_value::<Cpu>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "Cpu can not match 9");
+    }
+    #[test]
+    fn test_wide_repr() {
+        #[repr(usize)]
+        #[derive(Debug, Clone, PartialEq, NumToEnum)]
+        enum Size {
+            Small = 1,
+            Large = 2,
+        }
+        enum_to_bytes!(Size, usize);
+        let value: usize = Size::Large.into();
+        assert_eq!(value, 2);
+        assert_eq!(Size::try_from(1_usize), Ok(Size::Small));
+        let mut stream = Stream::empty();
+        stream.write_value(Size::Small).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<Size>().unwrap(), Size::Small);
+    }
+}
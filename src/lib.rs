@@ -1,13 +1,57 @@
+/*
+为 newtype 生成 From<内部类型>、Deref 和 DerefMut
+[泛型参数] 类型<类型参数>(内部类型, 其余字段的值)
+ */
+macro_rules! wrapper {
+    ($([$($generic:tt)*] $typ:ident<$($param:ident),*>($inner:ty $(, $rest:expr)*));* $(;)?) => {
+        $(
+            impl<$($generic)*> From<$inner> for $typ<$($param),*> {
+                fn from(value: $inner) -> Self {
+                    $typ(value $(, $rest)*)
+                }
+            }
+            impl<$($generic)*> std::ops::Deref for $typ<$($param),*> {
+                type Target = $inner;
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+            impl<$($generic)*> std::ops::DerefMut for $typ<$($param),*> {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    &mut self.0
+                }
+            }
+        )*
+    };
+}
+
 pub mod align;
+pub mod bits;
+#[cfg(feature = "file")]
+pub mod buffer;
 pub mod bytes;
+pub mod collections;
+pub mod dynamic;
 // #[cfg(feature = "crc32")]
 // pub mod crc32;
 pub mod endian;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "enum")]
+pub mod enums;
+pub mod patch;
 pub mod pin;
+pub mod prefix;
+pub mod slice;
+#[cfg(feature = "file")]
+pub mod spill;
 pub mod stream;
+pub mod varint;
 pub mod vec;
 #[cfg(feature = "deflate")]
 pub mod deflate;
 
 #[cfg(feature = "enum")]
 pub use derive;
+#[cfg(feature = "enum")]
+extern crate self as fast_stream;
@@ -0,0 +1,147 @@
+use crate::stream::Data;
+use memmap2::{Mmap, MmapMut};
+use std::fs::File;
+use std::io;
+use std::io::{Cursor, Error, ErrorKind};
+
+/*
+映射的文件内容,只读映射写入时返回 PermissionDenied
+ */
+#[derive(Debug)]
+pub enum MmapData {
+    ReadOnly(Mmap),
+    ReadWrite(MmapMut),
+}
+impl MmapData {
+    pub fn flush(&self) -> io::Result<()> {
+        match self {
+            MmapData::ReadOnly(_) => Ok(()),
+            MmapData::ReadWrite(data) => data.flush(),
+        }
+    }
+}
+impl AsRef<[u8]> for MmapData {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            MmapData::ReadOnly(data) => data,
+            MmapData::ReadWrite(data) => data,
+        }
+    }
+}
+impl std::ops::Deref for MmapData {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        self.as_ref()
+    }
+}
+
+/*
+只能在映射范围内写入,到达结尾后返回 0
+ */
+pub(crate) fn write(cursor: &mut Cursor<MmapData>, buf: &[u8]) -> io::Result<usize> {
+    let position = cursor.position();
+    let data = match cursor.get_mut() {
+        MmapData::ReadOnly(_) => {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "mmap stream is read only",
+            ));
+        }
+        MmapData::ReadWrite(data) => data,
+    };
+    let start = (position as usize).min(data.len());
+    let size = buf.len().min(data.len() - start);
+    data[start..start + size].copy_from_slice(&buf[..size]);
+    cursor.set_position(position + size as u64);
+    Ok(size)
+}
+
+impl Data {
+    /// 只读映射文件
+    ///
+    /// # Safety
+    /// 映射期间文件被其它进程修改或截断属于未定义行为
+    pub unsafe fn mmap(file: &File) -> io::Result<Self> {
+        Ok(unsafe { Mmap::map(file) }?.into())
+    }
+    /// 读写映射文件,文件需要以读写方式打开,长度不能修改
+    ///
+    /// # Safety
+    /// 同 [`Data::mmap`]
+    pub unsafe fn mmap_mut(file: &File) -> io::Result<Self> {
+        Ok(unsafe { MmapMut::map_mut(file) }?.into())
+    }
+}
+impl From<MmapData> for Data {
+    fn from(value: MmapData) -> Self {
+        Data::Mmap {
+            crc32: None,
+            sha1: None,
+            sha2: None,
+            data: Cursor::new(value),
+        }
+    }
+}
+impl From<Mmap> for Data {
+    fn from(value: Mmap) -> Self {
+        MmapData::ReadOnly(value).into()
+    }
+}
+impl From<MmapMut> for Data {
+    fn from(value: MmapMut) -> Self {
+        MmapData::ReadWrite(value).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::pin::Pin;
+    use crate::stream::{Data, Stream};
+    use std::fs::OpenOptions;
+    use std::io::{ErrorKind, Write};
+
+    #[test]
+    fn test_mmap() {
+        let path = std::env::temp_dir().join(format!("fast-stream-mmap-{}", std::process::id()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"\x01\x00ab\0cd").unwrap();
+
+        let mut stream = Stream::new(unsafe { Data::mmap_mut(&file) }.unwrap());
+        stream.init_crc32();
+        assert_eq!(stream.length(), 7);
+        assert_eq!(stream.read_value::<u16>().unwrap(), 1);
+        assert_eq!(stream.read_cstr().unwrap().to_bytes(), b"ab");
+        stream.write_all(b"ef").unwrap();
+        assert_eq!(stream.crc32_value(), crc32fast::hash(b"ef"));
+        assert_eq!(
+            stream.write_value(1_u8).unwrap_err().kind(),
+            ErrorKind::WriteZero
+        );
+        stream.flush().unwrap();
+        drop(stream);
+
+        let mut stream = Stream::new(unsafe { Data::mmap(&file) }.unwrap());
+        assert_eq!(stream.copy_data().unwrap(), b"\x01\x00ab\0ef");
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_slice(2).unwrap(), &[1, 0]);
+        assert_eq!(
+            stream.write_value(1_u8).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        /* 长度不能改变,失败后位置不变且不留下 pin */
+        stream.set_position(3).unwrap();
+        assert!(stream.splice(1, vec![0]).is_err());
+        assert!(stream.drain(1..2).is_err());
+        assert_eq!(stream.position().unwrap(), 3);
+        assert_eq!(stream.un_pin().unwrap_err().kind(), ErrorKind::NotFound);
+        drop(stream);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use crate::bytes::{Bytes, ValueWrite};
+use crate::endian::Endian;
+use crate::stream::Stream;
+use std::io;
+use std::io::{Error, ErrorKind, Seek, SeekFrom};
+use std::marker::PhantomData;
+
+/*
+reserve 写入的占位值,记录了位置和占用的字节数,之后通过 patch 回填
+ */
+#[derive(Debug)]
+pub struct Placeholder<Value> {
+    position: u64,
+    size: u64,
+    endian: Endian,
+    value: PhantomData<Value>,
+}
+impl<Value> Placeholder<Value> {
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+impl Stream {
+    pub fn reserve<Value: ValueWrite + Default>(&mut self) -> io::Result<Placeholder<Value>> {
+        let position = self.stream_position()?;
+        self.write_value(Value::default())?;
+        let size = self.stream_position()? - position;
+        Ok(Placeholder {
+            position,
+            size,
+            endian: self.endian.clone(),
+            value: PhantomData,
+        })
+    }
+    /*
+    回填占位值,当前位置保持不变,回填值的长度必须与占位一致,字节序使用占位时的字节序
+     */
+    pub fn patch<Value: ValueWrite>(
+        &mut self,
+        placeholder: Placeholder<Value>,
+        value: Value,
+    ) -> io::Result<&mut Self> {
+        let mut data = value.write(&placeholder.endian)?;
+        if data.length() != placeholder.size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "patch value size {} not equal placeholder size {}",
+                    data.length(),
+                    placeholder.size
+                ),
+            ));
+        }
+        data.seek_start()?;
+        /* 回填的字节已经在占位时参与过 hash,回填时暂停 hasher */
+        self.without_hash(|stream| {
+            stream.with_pin(|stream| {
+                stream.seek(SeekFrom::Start(placeholder.position))?;
+                stream.append(&mut data)
+            })
+        })?;
+        Ok(self)
+    }
+    #[cfg(feature = "crc32")]
+    pub fn crc32_range(&mut self, start: u64, end: u64) -> io::Result<u32> {
+        use std::io::Read;
+        let mut hasher = crc32fast::Hasher::new();
+        self.with_pin(|stream| {
+            stream.seek(SeekFrom::Start(start))?;
+            let mut remaining = end.saturating_sub(start);
+            let mut bytes = [0; 1024 * 4];
+            while remaining > 0 {
+                let size = (bytes.len() as u64).min(remaining) as usize;
+                stream.read_exact(&mut bytes[..size])?;
+                hasher.update(&bytes[..size]);
+                remaining -= size as u64;
+            }
+            Ok(())
+        })?;
+        Ok(hasher.finalize())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::stream::Stream;
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn test_patch() {
+        let mut stream = Stream::empty();
+        let size = stream.reserve::<u32>().unwrap();
+        let crc = stream.reserve::<u32>().unwrap();
+        stream.write_value(vec![1_u8, 2, 3]).unwrap();
+        let length = stream.length() as u32;
+        let crc32 = stream.crc32_range(8, stream.length()).unwrap();
+        stream.patch(size, length).unwrap();
+        stream.patch(crc, crc32).unwrap();
+        stream.write_value(9_u8).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<u32>().unwrap(), 19);
+        assert_eq!(
+            stream.read_value::<u32>().unwrap(),
+            crc32fast::hash(&[3, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3])
+        );
+        assert_eq!(stream.read_value::<Vec<u8>>().unwrap(), vec![1, 2, 3]);
+        assert_eq!(stream.read_value::<u8>().unwrap(), 9);
+
+        let placeholder = stream.reserve::<u16>().unwrap();
+        assert!(stream.patch(placeholder, 1_u16).is_ok());
+        let placeholder = stream.reserve::<Vec<u8>>().unwrap();
+        assert!(stream.patch(placeholder, vec![1]).is_err());
+
+        let mut stream = Stream::empty();
+        stream.init_crc32();
+        let placeholder = stream.reserve::<u16>().unwrap();
+        stream.write_value(1_u8).unwrap();
+        stream.patch(placeholder, 7_u16).unwrap();
+        assert_eq!(stream.crc32_value(), crc32fast::hash(&[0, 0, 1]));
+    }
+    #[cfg(all(feature = "enum", feature = "crc32"))]
+    #[test]
+    fn test_derive_patch() {
+        use crate::derive::{ValueRead, ValueWrite};
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Archive {
+            #[stream(offset_of = "data")]
+            offset: u16,
+            #[stream(len_of = "data")]
+            size: u32,
+            #[stream(crc32_of = "data")]
+            crc32: u32,
+            name: String,
+            data: Vec<u8>,
+        }
+        let archive = Archive {
+            offset: 0,
+            size: 0,
+            crc32: 0,
+            name: "a".to_string(),
+            data: vec![5, 6],
+        };
+        let mut stream = Stream::empty();
+        stream.write_value(archive).unwrap();
+        stream.seek_start().unwrap();
+        let archive: Archive = stream.read_value().unwrap();
+        assert_eq!(archive.offset, 12);
+        assert_eq!(archive.size, 10);
+        assert_eq!(
+            archive.crc32,
+            crc32fast::hash(&[2, 0, 0, 0, 0, 0, 0, 0, 5, 6])
+        );
+
+        #[derive(Debug, Clone, PartialEq, ValueRead, ValueWrite)]
+        struct Short {
+            #[stream(len_of = "data")]
+            size: u8,
+            data: Vec<u8>,
+        }
+        let mut stream = Stream::empty();
+        let error = stream
+            .write_value(Short {
+                size: 0,
+                data: vec![0; 300],
+            })
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -1,7 +1,9 @@
+use crate::bytes::{Bytes, ValueRead};
 use crate::stream::Stream;
 use std::fmt::Error;
 use std::io;
 use std::io::{ErrorKind, Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
 
 #[allow(dead_code)]
 pub trait Pin {
@@ -31,13 +33,14 @@ impl Pin for Stream {
         Err(io::Error::new(ErrorKind::NotFound, Error::default()))
     }
     /*
-    恢复到pin+size位置
+    恢复到pin+size位置,没有 pin 时返回 NotFound
      */
     fn un_pin_size(&mut self, size: u64) -> io::Result<&mut Self> {
         let current_position = self.data.borrow_mut().stream_position()?;
-        if let Some(position) = self.pins.borrow_mut().pop()
-            && current_position - position != size
-        {
+        let Some(position) = self.pins.borrow_mut().pop() else {
+            return Err(io::Error::new(ErrorKind::NotFound, "no pinned position"));
+        };
+        if current_position != position + size {
             self.data
                 .borrow_mut()
                 .seek(SeekFrom::Start(position + size))?;
@@ -53,3 +56,153 @@ impl Pin for Stream {
         self.stream_position()
     }
 }
+/*
+pin_guard 返回的守卫,drop 时恢复到 pin 的位置,commit 后保留当前位置
+守卫内部 pin/un_pin 不平衡时,drop 会把 pin 栈恢复到守卫创建前的深度
+ */
+#[must_use = "drop 时会立即恢复位置"]
+#[derive(Debug)]
+pub struct PinGuard<'a> {
+    stream: &'a mut Stream,
+    depth: usize,
+    position: u64,
+    commit: bool,
+}
+impl PinGuard<'_> {
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+    pub fn commit(mut self) {
+        self.commit = true;
+    }
+}
+impl Deref for PinGuard<'_> {
+    type Target = Stream;
+    fn deref(&self) -> &Self::Target {
+        self.stream
+    }
+}
+impl DerefMut for PinGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.stream
+    }
+}
+impl Drop for PinGuard<'_> {
+    fn drop(&mut self) {
+        self.stream.pins.borrow_mut().truncate(self.depth);
+        if !self.commit {
+            let _ = self
+                .stream
+                .data
+                .borrow_mut()
+                .seek(SeekFrom::Start(self.position));
+        }
+    }
+}
+impl Stream {
+    pub fn pin_guard(&mut self) -> io::Result<PinGuard<'_>> {
+        let depth = self.pins.borrow().len();
+        let position = self.pin()?;
+        Ok(PinGuard {
+            stream: self,
+            depth,
+            position,
+            commit: false,
+        })
+    }
+    /*
+    记录当前位置为 name,同名会覆盖
+     */
+    pub fn mark(&mut self, name: impl Into<String>) -> io::Result<u64> {
+        let position = self.stream_position()?;
+        self.marks.borrow_mut().insert(name.into(), position);
+        Ok(position)
+    }
+    pub fn seek_mark(&mut self, name: &str) -> io::Result<u64> {
+        let position = self.mark_position(name)?;
+        self.seek(SeekFrom::Start(position))
+    }
+    pub fn mark_position(&self, name: &str) -> io::Result<u64> {
+        self.marks.borrow().get(name).copied().ok_or(io::Error::new(
+            ErrorKind::NotFound,
+            format!("mark {} not found", name),
+        ))
+    }
+    pub fn remove_mark(&mut self, name: &str) -> io::Result<u64> {
+        self.marks.borrow_mut().remove(name).ok_or(io::Error::new(
+            ErrorKind::NotFound,
+            format!("mark {} not found", name),
+        ))
+    }
+    /*
+    执行 f 后恢复到执行前的位置,f 返回 Err 或 pin 之后没有 un_pin 时同样恢复
+     */
+    pub fn with_pin<Value>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> io::Result<Value>,
+    ) -> io::Result<Value> {
+        let depth = self.pins.borrow().len();
+        self.pin()?;
+        let value = f(self);
+        self.pins.borrow_mut().truncate(depth + 1);
+        self.un_pin()?;
+        value
+    }
+    pub fn peek_value<Value: ValueRead>(&mut self) -> io::Result<Value> {
+        self.with_pin(|stream| stream.read_value())
+    }
+    pub fn peek_bytes(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        self.with_pin(|stream| stream.read_exact_size(size))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::pin::Pin;
+    use crate::stream::Stream;
+
+    #[test]
+    fn test_peek() {
+        let mut stream: Stream = vec![1, 2, 3].into();
+        stream.read_value::<u8>().unwrap();
+        assert_eq!(stream.peek_value::<u8>().unwrap(), 2);
+        assert_eq!(stream.peek_bytes(2).unwrap(), vec![2, 3]);
+        assert!(stream.peek_bytes(3).is_err());
+        assert!(stream.peek_value::<u32>().is_err());
+        let value = stream.with_pin(|stream| {
+            stream.pin()?;
+            stream.read_value::<u16>()
+        });
+        assert_eq!(value.unwrap(), 0x0302);
+        assert_eq!(stream.position().unwrap(), 1);
+        assert!(stream.un_pin().is_err());
+    }
+    #[test]
+    fn test_pin_guard() {
+        let mut stream: Stream = vec![1, 2, 3, 4].into();
+        stream.read_value::<u8>().unwrap();
+        {
+            let mut guard = stream.pin_guard().unwrap();
+            guard.read_value::<u16>().unwrap();
+            guard.pin().unwrap();
+        }
+        assert_eq!(stream.position().unwrap(), 1);
+        assert!(stream.un_pin().is_err());
+        assert!(stream.un_pin_size(1).is_err());
+
+        let mut guard = stream.pin_guard().unwrap();
+        assert_eq!(guard.position(), 1);
+        guard.read_value::<u16>().unwrap();
+        guard.commit();
+        assert_eq!(stream.position().unwrap(), 3);
+
+        stream.mark("header").unwrap();
+        stream.set_position(0).unwrap();
+        assert_eq!(stream.seek_mark("header").unwrap(), 3);
+        assert_eq!(stream.read_value::<u8>().unwrap(), 4);
+        assert_eq!(
+            stream.seek_mark("body").unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::bytes::{Bytes, ValueRead, ValueWrite};
+use crate::stream::Stream;
+use crate::varint::VarU64;
+use std::io;
+use std::io::{Error, ErrorKind, Seek, Write};
+use std::marker::PhantomData;
+
+/*
+长度前缀的编码方式:u8/u16/u32/u64 按 stream 字节序,VarU64 为 LEB128
+ */
+pub trait LenPrefix: ValueRead + ValueWrite {
+    fn from_len(len: usize) -> io::Result<Self>;
+    fn to_len(self) -> u64;
+}
+macro_rules! len_prefix {
+    ($($typ:ty),*) => {
+        $(
+            impl LenPrefix for $typ {
+                fn from_len(len: usize) -> io::Result<Self> {
+                    <$typ>::try_from(len).map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("length {} overflow {}", len, stringify!($typ)),
+                        )
+                    })
+                }
+                fn to_len(self) -> u64 {
+                    self as u64
+                }
+            }
+        )*
+    };
+}
+len_prefix!(u8, u16, u32, u64);
+impl LenPrefix for VarU64 {
+    fn from_len(len: usize) -> io::Result<Self> {
+        Ok(VarU64(len as u64))
+    }
+    fn to_len(self) -> u64 {
+        self.0
+    }
+}
+
+/*
+元素个数前缀的 Vec,例如 PrefixVec<u16, Entry>
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct PrefixVec<P, T>(pub Vec<T>, pub PhantomData<P>);
+
+/*
+字节长度前缀的 String,不带结尾的 0
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct PrefixString<P>(pub String, pub PhantomData<P>);
+
+/*
+以 0 结尾的 String,写入时内容不能包含 0
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NulString(pub String);
+
+/*
+固定 N 个字节的 String,读取时去掉第一个 0 之后的内容,写入时补 0
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct FixedString<const N: usize>(pub String);
+
+/*
+读取到 stream 结尾的所有元素,没有前缀
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct RestVec<T>(pub Vec<T>);
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RestString(pub String);
+
+fn to_string(data: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(data)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("bytes to string {}", e)))
+}
+
+fn remaining(stream: &mut Stream) -> io::Result<u64> {
+    Ok(stream.length().saturating_sub(stream.stream_position()?))
+}
+
+impl<P: LenPrefix, T: 'static + ValueRead> ValueRead for PrefixVec<P, T> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len = P::read(stream)?.to_len();
+        Ok(PrefixVec(stream.read_values(len)?, PhantomData))
+    }
+}
+impl<P: LenPrefix, T: 'static + ValueWrite> ValueWrite for PrefixVec<P, T> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        P::from_len(self.0.len())?.write_to(stream)?;
+        stream.write_values(self.0)?;
+        Ok(())
+    }
+}
+
+impl<P: LenPrefix> ValueRead for PrefixString<P> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len = P::read(stream)?.to_len();
+        Ok(PrefixString(
+            to_string(stream.read_exact_size(len)?)?,
+            PhantomData,
+        ))
+    }
+}
+impl<P: LenPrefix> ValueWrite for PrefixString<P> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        P::from_len(self.0.len())?.write_to(stream)?;
+        stream.write_all(self.0.as_bytes())
+    }
+}
+
+impl ValueRead for NulString {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        Ok(NulString(stream.read_value()?))
+    }
+}
+impl ValueWrite for NulString {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        if self.0.as_bytes().contains(&0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "nul terminated string contains nul byte",
+            ));
+        }
+        self.0.write_to(stream)
+    }
+}
+
+impl<const N: usize> ValueRead for FixedString<N> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let mut data = stream.read_exact_size(N as u64)?;
+        if let Some(end) = data.iter().position(|byte| *byte == 0) {
+            data.truncate(end);
+        }
+        Ok(FixedString(to_string(data)?))
+    }
+}
+impl<const N: usize> ValueWrite for FixedString<N> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        if self.0.len() > N {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("string length {} overflow fixed size {}", self.0.len(), N),
+            ));
+        }
+        stream.write_all(self.0.as_bytes())?;
+        stream.write_all(&vec![0_u8; N - self.0.len()])
+    }
+}
+
+impl<T: 'static + ValueRead> ValueRead for RestVec<T> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let size = remaining(stream)?;
+        Ok(RestVec(stream.read_values_size(size)?))
+    }
+}
+impl<T: 'static + ValueWrite> ValueWrite for RestVec<T> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        stream.write_values(self.0)?;
+        Ok(())
+    }
+}
+
+impl ValueRead for RestString {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let size = remaining(stream)?;
+        Ok(RestString(to_string(stream.read_exact_size(size)?)?))
+    }
+}
+impl ValueWrite for RestString {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        stream.write_all(self.0.as_bytes())
+    }
+}
+
+wrapper!(
+    [P, T] PrefixVec<P, T>(Vec<T>, PhantomData);
+    [P] PrefixString<P>(String, PhantomData);
+    [] NulString<>(String);
+    [const N: usize] FixedString<N>(String);
+    [T] RestVec<T>(Vec<T>);
+    [] RestString<>(String);
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::prefix::{FixedString, NulString, PrefixString, PrefixVec, RestString, RestVec};
+    use crate::stream::Stream;
+    use crate::varint::VarU64;
+
+    #[test]
+    fn test_prefix() {
+        let mut stream = Stream::empty();
+        stream.with_big_endian();
+        stream
+            .write_value(PrefixVec::<u16, u32>::from(vec![1, 2]))
+            .unwrap()
+            .write_value(PrefixString::<u8>::from("ab".to_string()))
+            .unwrap()
+            .write_value(PrefixString::<VarU64>::from("c".to_string()))
+            .unwrap()
+            .write_value(NulString("d".to_string()))
+            .unwrap()
+            .write_value(FixedString::<4>("ef".to_string()))
+            .unwrap()
+            .write_value(RestVec(vec![3_u16]))
+            .unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![
+                0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 2, b'a', b'b', 1, b'c', b'd', 0, b'e', b'f', 0, 0, 0,
+                3
+            ]
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(
+            stream.read_value::<PrefixVec<u16, u32>>().unwrap().0,
+            vec![1, 2]
+        );
+        assert_eq!(*stream.read_value::<PrefixString<u8>>().unwrap(), "ab");
+        assert_eq!(*stream.read_value::<PrefixString<VarU64>>().unwrap(), "c");
+        assert_eq!(*stream.read_value::<NulString>().unwrap(), "d");
+        assert_eq!(*stream.read_value::<FixedString<4>>().unwrap(), "ef");
+        assert_eq!(*stream.read_value::<RestVec<u16>>().unwrap(), vec![3]);
+
+        assert!(
+            stream
+                .write_value(PrefixVec::<u8, u8>::from(vec![0; 256]))
+                .is_err()
+        );
+        assert!(
+            stream
+                .write_value(FixedString::<1>("ab".to_string()))
+                .is_err()
+        );
+        let mut stream: Stream = b"rest".to_vec().into();
+        assert_eq!(*stream.read_value::<RestString>().unwrap(), "rest");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::stream::{Data, Stream};
+use std::ffi::CStr;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+/*
+从 cursor 当前位置借用 len 个字节,位置移动到结尾
+ */
+fn cursor_slice<T: AsRef<[u8]>>(cursor: &mut Cursor<T>, len: u64) -> io::Result<&[u8]> {
+    let start = cursor.position();
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= cursor.get_ref().as_ref().len() as u64)
+        .ok_or(Error::new(
+            ErrorKind::UnexpectedEof,
+            "source stream not enought data",
+        ))?;
+    cursor.set_position(end);
+    Ok(&cursor.get_ref().as_ref()[start as usize..end as usize])
+}
+fn cursor_cstr<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> io::Result<&CStr> {
+    let start = (cursor.position() as usize).min(cursor.get_ref().as_ref().len());
+    let end = cursor.get_ref().as_ref()[start..]
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(Error::new(
+            ErrorKind::UnexpectedEof,
+            "nul terminator not found",
+        ))?
+        + start
+        + 1;
+    cursor.set_position(end as u64);
+    CStr::from_bytes_with_nul(&cursor.get_ref().as_ref()[start..end])
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+impl Stream {
+    /*
+    直接借用内存、mmap 或共享后端中的数据,不复制;其它后端返回 Unsupported
+     */
+    pub fn read_slice(&mut self, len: u64) -> io::Result<&[u8]> {
+        match self.data.get_mut() {
+            Data::Mem { data, .. } => cursor_slice(data, len),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => cursor_slice(data, len),
+            Data::Shared { data, .. } => cursor_slice(data, len),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::new(
+                ErrorKind::Unsupported,
+                "read_slice only supported by memory stream",
+            )),
+        }
+    }
+    /*
+    读取以 0 结尾的字符串,位置移动到 0 之后
+     */
+    pub fn read_cstr(&mut self) -> io::Result<&CStr> {
+        match self.data.get_mut() {
+            Data::Mem { data, .. } => cursor_cstr(data),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => cursor_cstr(data),
+            Data::Shared { data, .. } => cursor_cstr(data),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::new(
+                ErrorKind::Unsupported,
+                "read_cstr only supported by memory stream",
+            )),
+        }
+    }
+    /*
+    读取到 0 为止(不包含 0),内存、mmap 和共享后端直接查找,其它后端按块读取后回退多读的部分
+     */
+    pub fn read_until_nul(&mut self) -> io::Result<Vec<u8>> {
+        if self.read_slice(0).is_ok() {
+            return Ok(self.read_cstr()?.to_bytes().to_vec());
+        }
+        /* dyn 后端可能不能回退,逐字节读取 */
+        let chunk = if matches!(self.data.get_mut(), Data::Dyn { .. }) {
+            1
+        } else {
+            256
+        };
+        let mut bytes = vec![];
+        let mut buf = [0_u8; 256];
+        loop {
+            let size = self.read(&mut buf[..chunk])?;
+            if size == 0 {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "nul terminator not found",
+                ));
+            }
+            if let Some(index) = buf[..size].iter().position(|byte| *byte == 0) {
+                bytes.extend_from_slice(&buf[..index]);
+                self.seek(SeekFrom::Current(index as i64 + 1 - size as i64))?;
+                return Ok(bytes);
+            }
+            bytes.extend_from_slice(&buf[..size]);
+        }
+    }
+}
+
+/*
+共享的只读字节,Stream 直接从中读取;clone 和 copy_size 只增加引用计数,不复制数据
+ */
+#[derive(Clone)]
+pub struct SharedBytes {
+    bytes: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    start: usize,
+    end: usize,
+}
+impl SharedBytes {
+    pub fn new(bytes: impl AsRef<[u8]> + Send + Sync + 'static) -> Self {
+        let end = bytes.as_ref().len();
+        Self {
+            bytes: Arc::new(bytes),
+            start: 0,
+            end,
+        }
+    }
+    /*
+    同一份数据中 start..end 的一段,超出范围返回 None
+     */
+    pub fn slice(&self, start: usize, end: usize) -> Option<Self> {
+        if start > end || end > self.len() {
+            return None;
+        }
+        Some(Self {
+            bytes: self.bytes.clone(),
+            start: self.start + start,
+            end: self.start + end,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &(*self.bytes).as_ref()[self.start..self.end]
+    }
+}
+impl Debug for SharedBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBytes")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Data {
+    /*
+    只读后端,写入和修改长度返回 PermissionDenied
+     */
+    pub fn shared(bytes: impl AsRef<[u8]> + Send + Sync + 'static) -> Self {
+        SharedBytes::new(bytes).into()
+    }
+}
+impl From<SharedBytes> for Data {
+    fn from(value: SharedBytes) -> Self {
+        Data::Shared {
+            crc32: None,
+            sha1: None,
+            sha2: None,
+            data: Cursor::new(value),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{Bytes, ValueWrite};
+    use crate::endian::Endian;
+    use crate::stream::{Data, Stream};
+    use std::io::ErrorKind;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_slice() {
+        let mut stream: Stream = b"\x01\x02abc\0de\0f".to_vec().into();
+        assert_eq!(stream.read_slice(2).unwrap(), &[1, 2]);
+        assert_eq!(stream.read_cstr().unwrap().to_bytes(), b"abc");
+        assert_eq!(stream.read_value::<String>().unwrap(), "de");
+        assert_eq!(
+            stream.read_cstr().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            stream.read_slice(2).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(stream.read_slice(1).unwrap(), b"f");
+    }
+    #[test]
+    fn test_shared() {
+        let bytes: Arc<[u8]> = Arc::from(&b"\x01\x00ab\0\x03\x00\x00\x00cd"[..]);
+        let mut stream = Stream::new(Data::shared(bytes.clone()));
+        assert_eq!(stream.length(), 11);
+        assert_eq!(stream.read_value::<u16>().unwrap(), 1);
+        assert_eq!(stream.read_cstr().unwrap().to_bytes(), b"ab");
+        assert_eq!(stream.read_values_size::<u32>(4).unwrap(), vec![3]);
+        /* copy_size 与原数据共享同一块内存 */
+        let mut rest = stream.copy_size(2).unwrap();
+        assert_eq!(rest.read_slice(2).unwrap().as_ptr(), bytes[9..].as_ptr());
+        assert_eq!(
+            stream.read_slice(1).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_slice(2).unwrap().as_ptr(), bytes.as_ptr());
+        assert_eq!(
+            stream.write_value(1_u8).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            stream.splice(0, vec![1]).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            stream.drain(0..1).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(stream.length(), 11);
+        assert_eq!(stream.copy_data().unwrap(), &bytes[..]);
+    }
+    #[test]
+    fn test_stream_holder() {
+        /* Stream 不带生命周期参数,可以直接作为字段 */
+        struct Holder {
+            stream: Stream,
+        }
+        let holder = Holder {
+            stream: ValueWrite::write(4_u16, &Endian::Little).unwrap(),
+        };
+        assert_eq!(holder.stream.length(), 2);
+        let mut holder = Holder {
+            stream: Stream::new(Data::shared(vec![4, 0])),
+        };
+        assert_eq!(holder.stream.read_value::<u16>().unwrap(), 4);
+    }
+    #[cfg(feature = "file")]
+    #[test]
+    fn test_read_until_nul_file() {
+        use std::io::Write;
+
+        let mut stream = Stream::new(tempfile::tempfile().unwrap().into());
+        let mut data = vec![b'a'; 300];
+        data.extend_from_slice(b"\0bc\0");
+        stream.write_all(&data).unwrap();
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<String>().unwrap().len(), 300);
+        assert_eq!(stream.read_value::<String>().unwrap(), "bc");
+        assert_eq!(
+            stream.read_slice(1).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+    }
+}
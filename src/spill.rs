@@ -0,0 +1,168 @@
+use crate::buffer::BufFile;
+use crate::stream::{Data, Stream};
+use std::io;
+use std::io::{Seek, SeekFrom, Write};
+
+impl Data {
+    /*
+    内存数据迁移到临时文件,保留当前位置与正在计算的 hasher,其它后端不处理
+    返回是否发生了迁移
+     */
+    pub(crate) fn spill(&mut self) -> io::Result<bool> {
+        let Data::Mem {
+            crc32,
+            sha1,
+            sha2,
+            data,
+        } = self
+        else {
+            return Ok(false);
+        };
+        let mut file = tempfile::tempfile()?;
+        file.write_all(data.get_ref())?;
+        file.seek(SeekFrom::Start(data.position()))?;
+        *self = Data::File {
+            crc32: crc32.take(),
+            sha1: sha1.take(),
+            sha2: sha2.take(),
+            data: BufFile::new(file),
+        };
+        Ok(true)
+    }
+}
+impl Stream {
+    /*
+    内存数据超过 threshold 字节时自动迁移到临时文件
+     */
+    pub fn with_spill(&mut self, threshold: u64) -> &mut Self {
+        self.spill = Some(threshold);
+        self
+    }
+    /*
+    立即迁移到临时文件
+     */
+    pub fn spill(&mut self) -> io::Result<&mut Self> {
+        if self.data.get_mut().spill()? {
+            *self.spilled.get_mut() = true;
+        }
+        Ok(self)
+    }
+    /*
+    是否由内存迁移到了临时文件,本来就是文件的 stream 返回 false
+     */
+    pub fn is_spilled(&self) -> bool {
+        *self.spilled.borrow()
+    }
+    /*
+    写入后结尾会超过阈值时先迁移
+     */
+    pub(crate) fn spill_check(&self, end: u64) -> io::Result<()> {
+        if self.spill.is_some_and(|threshold| end > threshold) && self.data.borrow_mut().spill()? {
+            *self.spilled.borrow_mut() = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::align::Align;
+    use crate::bytes::{Bytes, ValueWrite};
+    use crate::pin::Pin;
+    use crate::stream::Stream;
+    use std::io;
+    use std::io::{Read, Seek, SeekFrom};
+
+    #[test]
+    fn test_spill() {
+        let mut stream = Stream::empty();
+        stream.with_spill(8).init_crc32();
+        stream
+            .write_value(1_u32)
+            .unwrap()
+            .write_value(2_u32)
+            .unwrap();
+        assert!(!stream.is_spilled());
+        stream.seek(SeekFrom::Start(4)).unwrap();
+        stream.pin().unwrap();
+        stream.write_value(3_u64).unwrap();
+        assert!(stream.is_spilled());
+        assert_eq!(stream.length(), 12);
+        assert_eq!(
+            stream.crc32_value(),
+            crc32fast::hash(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0])
+        );
+        stream.un_pin().unwrap();
+        assert_eq!(stream.read_value::<u64>().unwrap(), 3);
+        stream.seek_start().unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![1, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+    #[test]
+    fn test_spill_paths() {
+        let mut stream = Stream::empty();
+        stream.with_spill(8);
+        let mut source: Stream = vec![7; 100].into();
+        stream.append(&mut source).unwrap();
+        assert!(stream.is_spilled());
+        assert_eq!(stream.length(), 100);
+
+        /* 先 write 到新的 Stream 再 append 的旧写法 */
+        struct Legacy(u64, u64);
+        impl ValueWrite for Legacy {
+            fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+                let mut data = Stream::empty();
+                data.with_endian(stream.endian.clone());
+                data.write_value(self.0)?.write_value(self.1)?;
+                data.seek_start()?;
+                stream.append(&mut data)?;
+                Ok(())
+            }
+        }
+        let mut stream = Stream::empty();
+        stream.with_spill(8).write_value(Legacy(1, 2)).unwrap();
+        assert!(stream.is_spilled());
+        assert_eq!(stream.length(), 16);
+
+        let mut stream = Stream::empty();
+        stream.with_spill(8).fill_size(100).unwrap();
+        assert!(stream.is_spilled());
+        assert_eq!(stream.length(), 100);
+
+        let mut stream: Stream = vec![1; 7].into();
+        stream.with_spill(8);
+        stream.seek(SeekFrom::End(0)).unwrap();
+        Align::align(&mut stream, 16).unwrap();
+        assert!(stream.is_spilled());
+        stream.seek_start().unwrap();
+        let mut data = vec![];
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data.len(), 16);
+
+        let mut stream = Stream::new(tempfile::tempfile().unwrap().into());
+        stream.with_spill(8).fill_size(100).unwrap();
+        assert!(!stream.is_spilled());
+        stream.spill().unwrap();
+        assert!(!stream.is_spilled());
+    }
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn test_spill_deflate() {
+        use crate::deflate::{CompressionLevel, Deflate};
+
+        let mut stream: Stream = vec![0; 4].into();
+        stream.with_spill(64);
+        stream.compress(&CompressionLevel::BestSpeed).unwrap();
+        assert!(!stream.is_spilled());
+        assert_eq!(stream.decompress().unwrap(), 4);
+        assert!(!stream.is_spilled());
+
+        let mut stream: Stream = (0..=255).collect::<Vec<u8>>().into();
+        stream.compress(&CompressionLevel::NoCompression).unwrap();
+        stream.with_spill(64);
+        assert_eq!(stream.decompress().unwrap(), 256);
+        assert!(stream.is_spilled());
+    }
+}
@@ -1,20 +1,26 @@
+use crate::dynamic::ReadWriteSeek;
 use crate::endian::Endian;
 use crate::pin::Pin;
 use crc32fast::Hasher;
 use sha1::{Digest, Sha1};
 use sha2::Sha256;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io;
 use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom, Write};
 
 #[derive(Debug)]
 pub enum Data {
+    /*
+    data 由 std::fs::File 改为带缓冲的 BufFile,直接匹配这个字段的代码需要调整:
+    用 data.get_mut() 或 data.into_inner() 取得 File,两者都会先落盘
+     */
     #[cfg(feature = "file")]
     File {
         crc32: Option<Hasher>,
         sha1: Option<Sha1>,
         sha2: Option<Sha256>,
-        data: std::fs::File,
+        data: crate::buffer::BufFile,
     },
     Mem {
         crc32: Option<Hasher>,
@@ -22,109 +28,94 @@ pub enum Data {
         sha2: Option<Sha256>,
         data: Cursor<Vec<u8>>,
     },
+    #[cfg(feature = "mmap")]
+    Mmap {
+        crc32: Option<Hasher>,
+        sha1: Option<Sha1>,
+        sha2: Option<Sha256>,
+        data: Cursor<crate::mmap::MmapData>,
+    },
+    /*
+    多个 stream 共享的只读字节,读取时不复制
+     */
+    Shared {
+        crc32: Option<Hasher>,
+        sha1: Option<Sha1>,
+        sha2: Option<Sha256>,
+        data: Cursor<crate::slice::SharedBytes>,
+    },
+    /*
+    用户提供的任意 Read + Write + Seek 后端
+     */
+    Dyn {
+        crc32: Option<Hasher>,
+        sha1: Option<Sha1>,
+        sha2: Option<Sha256>,
+        data: Box<dyn ReadWriteSeek>,
+    },
 }
 impl Data {
-    pub(crate) fn init_sha(&mut self) {
+    fn hashers(&mut self) -> (&mut Option<Hasher>, &mut Option<Sha1>, &mut Option<Sha256>) {
         match self {
             #[cfg(feature = "file")]
-            Data::File { sha1, sha2, .. } => {
-                *sha1 = Some(Sha1::new());
-                *sha2 = Some(Sha256::new());
-            }
-            Data::Mem { sha1, sha2, .. } => {
-                *sha1 = Some(Sha1::new());
-                *sha2 = Some(Sha256::new());
-            }
+            Data::File {
+                crc32, sha1, sha2, ..
+            } => (crc32, sha1, sha2),
+            Data::Mem {
+                crc32, sha1, sha2, ..
+            } => (crc32, sha1, sha2),
+            #[cfg(feature = "mmap")]
+            Data::Mmap {
+                crc32, sha1, sha2, ..
+            } => (crc32, sha1, sha2),
+            Data::Shared {
+                crc32, sha1, sha2, ..
+            } => (crc32, sha1, sha2),
+            Data::Dyn {
+                crc32, sha1, sha2, ..
+            } => (crc32, sha1, sha2),
         }
     }
+    pub(crate) fn init_sha(&mut self) {
+        let (_, sha1, sha2) = self.hashers();
+        *sha1 = Some(Sha1::new());
+        *sha2 = Some(Sha256::new());
+    }
     pub(crate) fn init_crc32(&mut self) {
-        match self {
-            #[cfg(feature = "file")]
-            Data::File { crc32, .. } => {
-                *crc32 = Some(Hasher::new());
-            }
-            Data::Mem { crc32, .. } => {
-                *crc32 = Some(Hasher::new());
-            }
-        }
+        let (crc32, _, _) = self.hashers();
+        *crc32 = Some(Hasher::new());
     }
     pub fn hash_update(&mut self, data: &[u8]) -> Result<(), Error> {
-        match self {
-            #[cfg(feature = "file")]
-            Data::File {
-                sha1, sha2, crc32, ..
-            } => {
-                if let Some(crc32) = crc32 {
-                    crc32.update(data);
-                }
-                if let Some(sha1) = sha1 {
-                    sha1.update(data);
-                }
-                if let Some(sha2) = sha2 {
-                    sha2.update(data);
-                }
-            }
-            Data::Mem {
-                sha1, sha2, crc32, ..
-            } => {
-                if let Some(crc32) = crc32 {
-                    crc32.update(data);
-                }
-                if let Some(sha1) = sha1 {
-                    sha1.update(data);
-                }
-                if let Some(sha2) = sha2 {
-                    sha2.update(data);
-                }
-            }
+        let (crc32, sha1, sha2) = self.hashers();
+        if let Some(crc32) = crc32 {
+            crc32.update(data);
+        }
+        if let Some(sha1) = sha1 {
+            sha1.update(data);
+        }
+        if let Some(sha2) = sha2 {
+            sha2.update(data);
         }
         Ok(())
     }
     pub fn crc32_value(&mut self) -> u32 {
-        match self {
-            #[cfg(feature = "file")]
-            Data::File { crc32, .. } => {
-                if let Some(crc32) = crc32.take() {
-                    return crc32.finalize();
-                }
-            }
-            Data::Mem { crc32, .. } => {
-                if let Some(crc32) = crc32.take() {
-                    return crc32.finalize();
-                }
-            }
+        let (crc32, _, _) = self.hashers();
+        if let Some(crc32) = crc32.take() {
+            return crc32.finalize();
         }
         0
     }
     pub fn sha1_value(&mut self) -> Vec<u8> {
-        match self {
-            #[cfg(feature = "file")]
-            Data::File { sha1, .. } => {
-                if let Some(sha1) = sha1.take() {
-                    return sha1.finalize().to_vec();
-                }
-            }
-            Data::Mem { sha1, .. } => {
-                if let Some(sha1) = sha1.take() {
-                    return sha1.finalize().to_vec();
-                }
-            }
+        let (_, sha1, _) = self.hashers();
+        if let Some(sha1) = sha1.take() {
+            return sha1.finalize().to_vec();
         }
         vec![]
     }
     pub fn sha2_value(&mut self) -> Vec<u8> {
-        match self {
-            #[cfg(feature = "file")]
-            Data::File { sha2, .. } => {
-                if let Some(sha2) = sha2.take() {
-                    return sha2.finalize().to_vec();
-                }
-            }
-            Data::Mem { sha2, .. } => {
-                if let Some(sha2) = sha2.take() {
-                    return sha2.finalize().to_vec();
-                }
-            }
+        let (_, _, sha2) = self.hashers();
+        if let Some(sha2) = sha2.take() {
+            return sha2.finalize().to_vec();
         }
         vec![]
     }
@@ -140,8 +131,45 @@ impl Data {
                 data.get_mut().clear();
                 ()
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { .. } => self.set_len(0)?,
+            Data::Shared { .. } => self.set_len(0)?,
+            Data::Dyn { .. } => self.set_len(0)?,
         })
     }
+    /*
+    修改数据长度,变长时补 0;mmap 的映射长度固定,共享的字节只读,都不能修改,dyn 不能截断
+     */
+    pub(crate) fn set_len(&mut self, len: u64) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "file")]
+            Data::File { data, .. } => data.set_len(len),
+            Data::Mem { data, .. } => {
+                data.get_mut().resize(len as usize, 0);
+                Ok(())
+            }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => {
+                if data.get_ref().len() as u64 == len {
+                    return Ok(());
+                }
+                Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "mmap stream length can not be changed",
+                ))
+            }
+            Data::Shared { data, .. } => {
+                if data.get_ref().len() as u64 == len {
+                    return Ok(());
+                }
+                Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    "shared stream is read only",
+                ))
+            }
+            Data::Dyn { data, .. } => crate::dynamic::set_len(data.as_mut(), len),
+        }
+    }
     pub fn clone(&mut self) -> io::Result<Self> {
         Ok(match self {
             #[cfg(feature = "file")]
@@ -164,6 +192,17 @@ impl Data {
                     sha2: None,
                 }
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.get_ref().to_vec().into(),
+            Data::Shared { data, .. } => data.get_ref().clone().into(),
+            Data::Dyn { data, .. } => {
+                let position = data.stream_position()?;
+                data.seek(SeekFrom::Start(0))?;
+                let mut copy = vec![];
+                data.read_to_end(&mut copy)?;
+                data.seek(SeekFrom::Start(position))?;
+                copy.into()
+            }
         })
     }
     pub fn copy_data(&mut self) -> io::Result<Vec<u8>> {
@@ -176,6 +215,14 @@ impl Data {
                 data
             }
             Data::Mem { data, .. } => data.get_ref().to_vec(),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.get_ref().to_vec(),
+            Data::Shared { data, .. } => data.get_ref().as_ref().to_vec(),
+            Data::Dyn { data, .. } => {
+                let mut copy = vec![];
+                data.read_to_end(&mut copy)?;
+                copy
+            }
         };
         Ok(data)
     }
@@ -187,7 +234,7 @@ impl Data {
 impl From<std::fs::File> for Data {
     fn from(value: std::fs::File) -> Self {
         Data::File {
-            data: value,
+            data: crate::buffer::BufFile::new(value),
             crc32: None,
             sha1: None,
             sha2: None,
@@ -210,6 +257,10 @@ impl Seek for Data {
             #[cfg(feature = "file")]
             Data::File { data, .. } => data.seek(pos),
             Data::Mem { data, .. } => data.seek(pos),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.seek(pos),
+            Data::Shared { data, .. } => data.seek(pos),
+            Data::Dyn { data, .. } => data.seek(pos),
         }
     }
 }
@@ -219,50 +270,31 @@ impl Read for Data {
             #[cfg(feature = "file")]
             Data::File { data, .. } => data.read(buf),
             Data::Mem { data, .. } => data.read(buf),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.read(buf),
+            Data::Shared { data, .. } => data.read(buf),
+            Data::Dyn { data, .. } => data.read(buf),
         }
     }
 }
 impl Write for Data {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        match self {
+        let bytes = match self {
             #[cfg(feature = "file")]
-            Data::File {
-                data,
-                sha1,
-                sha2,
-                crc32,
-                ..
-            } => {
-                if let Some(crc32) = crc32 {
-                    crc32.update(buf);
-                }
-                if let Some(sha1) = sha1 {
-                    sha1.update(buf);
-                }
-                if let Some(sha2) = sha2 {
-                    sha2.update(buf);
-                }
-                data.write(buf)
-            }
-            Data::Mem {
-                data,
-                sha1,
-                sha2,
-                crc32,
-                ..
-            } => {
-                if let Some(crc32) = crc32 {
-                    crc32.update(buf);
-                }
-                if let Some(sha1) = sha1 {
-                    sha1.update(buf);
-                }
-                if let Some(sha2) = sha2 {
-                    sha2.update(buf);
-                }
-                data.write(buf)
+            Data::File { data, .. } => data.write(buf)?,
+            Data::Mem { data, .. } => data.write(buf)?,
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => crate::mmap::write(data, buf)?,
+            Data::Shared { .. } => {
+                return Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    "shared stream is read only",
+                ));
             }
-        }
+            Data::Dyn { data, .. } => data.write(buf)?,
+        };
+        self.hash_update(&buf[..bytes])?;
+        Ok(bytes)
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -270,6 +302,10 @@ impl Write for Data {
             #[cfg(feature = "file")]
             Data::File { data, .. } => data.flush(),
             Data::Mem { data, .. } => data.flush(),
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.get_ref().flush(),
+            Data::Shared { .. } => Ok(()),
+            Data::Dyn { data, .. } => data.flush(),
         }
     }
 }
@@ -280,6 +316,9 @@ pub struct Stream {
     pub endian: Endian,
     pub(crate) length: RefCell<u64>,
     pub(crate) pins: RefCell<Vec<u64>>,
+    pub(crate) marks: RefCell<HashMap<String, u64>>,
+    pub(crate) spill: Option<u64>,
+    pub(crate) spilled: RefCell<bool>,
 }
 impl Stream {
     pub fn sha1_value(&mut self) -> Vec<u8> {
@@ -291,6 +330,20 @@ impl Stream {
     pub fn sha2_value(&mut self) -> Vec<u8> {
         self.data.borrow_mut().sha2_value()
     }
+    /*
+    暂停 hasher,f 中读写的数据不参与 crc32/sha 计算
+     */
+    pub(crate) fn without_hash<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> io::Result<R>,
+    ) -> io::Result<R> {
+        let (crc32, sha1, sha2) = self.data.get_mut().hashers();
+        let hashers = (crc32.take(), sha1.take(), sha2.take());
+        let result = f(self);
+        let (crc32, sha1, sha2) = self.data.get_mut().hashers();
+        (*crc32, *sha1, *sha2) = hashers;
+        result
+    }
 }
 impl From<Vec<u8>> for Stream {
     fn from(value: Vec<u8>) -> Self {
@@ -300,6 +353,9 @@ impl From<Vec<u8>> for Stream {
             endian: Endian::Little,
             length: RefCell::new(length),
             pins: RefCell::new(vec![]),
+            marks: RefCell::new(HashMap::new()),
+            spill: None,
+            spilled: RefCell::new(false),
         }
     }
 }
@@ -403,6 +459,28 @@ impl Stream {
                 // s.with_endian(self.endian.clone());
                 // s
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { .. } => {
+                let data = stream.read_slice(size as u64).map_err(|_| {
+                    Error::new(ErrorKind::InvalidData, "source stream not enought data")
+                })?;
+                self.write_all(data)?;
+            }
+            Data::Shared { .. } => {
+                let data = stream.read_slice(size as u64).map_err(|_| {
+                    Error::new(ErrorKind::InvalidData, "source stream not enought data")
+                })?;
+                self.write_all(data)?;
+            }
+            Data::Dyn { data, .. } => {
+                let copied = std::io::copy(&mut data.as_mut().take(size as u64), self)?;
+                if copied < size as u64 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "source stream not enought data",
+                    ));
+                }
+            }
         })
     }
     pub fn copy_size(&mut self, size: usize) -> io::Result<Self> {
@@ -436,7 +514,43 @@ impl Stream {
             }
             Data::Mem { data, .. } => {
                 let position = data.position() as usize;
-                let mut s = Self::new(data.get_ref()[position..position + size].to_vec().into());
+                let copy = position
+                    .checked_add(size)
+                    .and_then(|end| data.get_ref().get(position..end))
+                    .ok_or(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "source stream not enought data",
+                    ))?
+                    .to_vec();
+                let mut s = Self::new(copy.into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Dyn { data, .. } => {
+                let mut copy = vec![0_u8; size];
+                data.read_exact(&mut copy)?;
+                let mut s = Self::new(copy.into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { .. } => {
+                let mut s = Self::new(self.read_slice(size as u64)?.to_vec().into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            /* 共享同一份数据,不复制 */
+            Data::Shared { data, .. } => {
+                let position = data.position() as usize;
+                let bytes = position
+                    .checked_add(size)
+                    .and_then(|end| data.get_ref().slice(position, end))
+                    .ok_or(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "source stream not enought data",
+                    ))?;
+                data.set_position((position + size) as u64);
+                let mut s = Self::new(bytes.into());
                 s.with_endian(self.endian.clone());
                 s
             }
@@ -457,6 +571,22 @@ impl Stream {
                 s.with_endian(self.endian.clone());
                 s
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { .. } => {
+                let mut s = Self::new(vec![].into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Shared { .. } => {
+                let mut s = Self::new(vec![].into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Dyn { .. } => {
+                let mut s = Self::new(vec![].into());
+                s.with_endian(self.endian.clone());
+                s
+            }
         })
     }
     pub fn copy_empty_with_capacity(&self, capacity: usize) -> io::Result<Self> {
@@ -474,6 +604,22 @@ impl Stream {
                 s.with_endian(self.endian.clone());
                 s
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { .. } => {
+                let mut s = Self::new(Vec::with_capacity(capacity).into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Shared { .. } => {
+                let mut s = Self::new(Vec::with_capacity(capacity).into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Dyn { .. } => {
+                let mut s = Self::new(Vec::with_capacity(capacity).into());
+                s.with_endian(self.endian.clone());
+                s
+            }
         })
     }
     pub fn copy_empty_same_capacity(&self) -> io::Result<Self> {
@@ -491,6 +637,22 @@ impl Stream {
                 s.with_endian(self.endian.clone());
                 s
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => {
+                let mut s = Self::new(Vec::with_capacity(data.get_ref().len()).into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Shared { data, .. } => {
+                let mut s = Self::new(Vec::with_capacity(data.get_ref().len()).into());
+                s.with_endian(self.endian.clone());
+                s
+            }
+            Data::Dyn { .. } => {
+                let mut s = Self::new(vec![].into());
+                s.with_endian(self.endian.clone());
+                s
+            }
         })
     }
     pub fn length(&self) -> u64 {
@@ -508,19 +670,25 @@ impl Stream {
         self.endian = Endian::Big;
         self
     }
-    pub fn capacity(value: usize) -> Stream {
+    pub fn capacity(value: usize) -> Self {
         Self {
             data: RefCell::new(Vec::with_capacity(value).into()),
             endian: Endian::Little,
             pins: RefCell::new(vec![]),
+            marks: RefCell::new(HashMap::new()),
+            spill: None,
+            spilled: RefCell::new(false),
             length: RefCell::new(0),
         }
     }
-    pub fn empty() -> Stream {
+    pub fn empty() -> Self {
         Self {
             data: RefCell::new(vec![].into()),
             endian: Endian::Little,
             pins: RefCell::new(vec![]),
+            marks: RefCell::new(HashMap::new()),
+            spill: None,
+            spilled: RefCell::new(false),
             length: RefCell::new(0),
         }
     }
@@ -537,16 +705,23 @@ impl Stream {
     pub fn init_crc32(&mut self) {
         self.data.borrow_mut().init_crc32();
     }
-    pub fn new(data: Data) -> Stream {
-        let length = match &data {
+    pub fn new(mut data: Data) -> Self {
+        let length = match &mut data {
             #[cfg(feature = "file")]
-            Data::File { data, .. } => data.metadata().unwrap().len(),
+            Data::File { data, .. } => data.length().unwrap(),
             Data::Mem { data, .. } => data.get_ref().len() as u64,
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => data.get_ref().len() as u64,
+            Data::Shared { data, .. } => data.get_ref().len() as u64,
+            Data::Dyn { data, .. } => crate::dynamic::length(data.as_mut()).unwrap(),
         };
         Self {
             data: RefCell::new(data),
             endian: Endian::Little,
             pins: RefCell::new(vec![]),
+            marks: RefCell::new(HashMap::new()),
+            spill: None,
+            spilled: RefCell::new(false),
             length: RefCell::new(length),
         }
     }
@@ -564,6 +739,8 @@ impl Read for Stream {
 impl Write for Stream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let position = self.stream_position()?;
+        #[cfg(feature = "file")]
+        self.spill_check(position + buf.len() as u64)?;
         let bytes = self.data.borrow_mut().write(buf)?;
         if *self.length.borrow() == position {
             *self.length.borrow_mut() += bytes as u64;
@@ -584,15 +761,15 @@ impl Write for Stream {
 mod tests {
     use crate::bytes::Bytes;
     use crate::stream::Stream;
-    use std::io::{Seek, SeekFrom};
+    use std::io::{Seek, SeekFrom, Write};
 
     #[test]
     fn test_merge() {
         let mut data = Stream::new(vec![1, 2, 3].into());
         data.seek(SeekFrom::End(0)).unwrap();
-        let writer = Stream::new(vec![0, 0, 0].into());
-        data.merge(writer).unwrap();
-        assert_eq!(data.length, 6);
+        let mut writer = Stream::new(vec![0, 0, 0].into());
+        data.append(&mut writer).unwrap();
+        assert_eq!(data.length(), 6);
         assert_eq!(data.take_data().unwrap(), vec![1, 2, 3, 0, 0, 0]);
     }
 
@@ -600,19 +777,19 @@ mod tests {
     fn test_write_vec() {
         let mut data = Stream::new(vec![1, 2, 3].into());
         data.seek(SeekFrom::End(0)).unwrap();
-        data.write_value(&vec![0, 0, 0]).unwrap();
+        data.write_all(&[0, 0, 0]).unwrap();
         assert_eq!(data.take_data().unwrap(), vec![1, 2, 3, 0, 0, 0]);
         let mut data = Stream::new(vec![1, 2, 3].into());
         data.seek(SeekFrom::Start(0)).unwrap();
-        data.write_value(&vec![0, 0, 0]).unwrap();
+        data.write_all(&[0, 0, 0]).unwrap();
         assert_eq!(data.take_data().unwrap(), vec![0, 0, 0]);
         let mut data = Stream::new(vec![1, 2, 3].into());
         data.seek(SeekFrom::Start(1)).unwrap();
-        data.write_value(&vec![0, 0, 0]).unwrap();
+        data.write_all(&[0, 0, 0]).unwrap();
         assert_eq!(data.take_data().unwrap(), vec![1, 0, 0, 0]);
         let mut data = Stream::new(vec![1, 2, 3].into());
         data.seek(SeekFrom::Start(1)).unwrap();
-        data.write_value(&vec![0]).unwrap();
+        data.write_all(&[0]).unwrap();
         assert_eq!(data.take_data().unwrap(), vec![1, 0, 3]);
     }
 }
@@ -0,0 +1,229 @@
+use crate::bytes::{Bytes, ValueRead, ValueWrite};
+use crate::stream::Stream;
+use std::io;
+use std::io::{Error, ErrorKind, Write};
+
+/*
+无符号 LEB128(protobuf varint、DWARF ULEB128、wasm u32/u64)
+ */
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VarU64(pub u64);
+
+/*
+zigzag 编码后按 LEB128 存储(protobuf sint64)
+ */
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VarI64(pub i64);
+
+/*
+有符号 LEB128(DWARF SLEB128、wasm i32/i64)
+ */
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SLeb64(pub i64);
+
+/*
+VarU64 长度前缀的 Vec 与 String(String 不带结尾的 0)
+ */
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct VarVec<T>(pub Vec<T>);
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VarString(pub String);
+
+/*
+返回值、已读取的 bit 数与最后一个字节,最多 10 个字节
+ */
+fn read_leb128(stream: &mut Stream) -> io::Result<(u64, u32, u8)> {
+    let mut value = 0_u64;
+    let mut shift = 0_u32;
+    loop {
+        if shift >= 70 {
+            return Err(Error::new(ErrorKind::InvalidData, "varint overflow u64"));
+        }
+        let byte: u8 = stream.read_value()?;
+        if shift < 64 {
+            value |= ((byte & 0x7F) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok((value, shift, byte));
+        }
+    }
+}
+
+impl ValueRead for VarU64 {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let (value, shift, byte) = read_leb128(stream)?;
+        if shift == 70 && byte > 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "varint overflow u64"));
+        }
+        Ok(VarU64(value))
+    }
+}
+impl ValueWrite for VarU64 {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        let mut value = self.0;
+        let mut data = [0_u8; 10];
+        let mut len = 0;
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                data[len] = byte;
+                return stream.write_all(&data[..=len]);
+            }
+            data[len] = byte | 0x80;
+            len += 1;
+        }
+    }
+}
+
+impl ValueRead for VarI64 {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let value = VarU64::read(stream)?.0;
+        Ok(VarI64((value >> 1) as i64 ^ -((value & 1) as i64)))
+    }
+}
+impl ValueWrite for VarI64 {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        VarU64(((self.0 << 1) ^ (self.0 >> 63)) as u64).write_to(stream)
+    }
+}
+
+impl ValueRead for SLeb64 {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let (value, shift, byte) = read_leb128(stream)?;
+        if shift == 70 {
+            /*
+            第 10 个字节只有最低位有效,其余位必须是符号扩展
+             */
+            if byte != 0 && byte != 0x7F {
+                return Err(Error::new(ErrorKind::InvalidData, "varint overflow i64"));
+            }
+            return Ok(SLeb64(value as i64));
+        }
+        if byte & 0x40 != 0 {
+            return Ok(SLeb64((value | (u64::MAX << shift)) as i64));
+        }
+        Ok(SLeb64(value as i64))
+    }
+}
+impl ValueWrite for SLeb64 {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        let mut value = self.0;
+        let mut data = [0_u8; 10];
+        let mut len = 0;
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+                data[len] = byte;
+                return stream.write_all(&data[..=len]);
+            }
+            data[len] = byte | 0x80;
+            len += 1;
+        }
+    }
+}
+
+impl<T: 'static + ValueRead> ValueRead for VarVec<T> {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len = VarU64::read(stream)?.0;
+        Ok(VarVec(stream.read_values(len)?))
+    }
+}
+impl<T: 'static + ValueWrite> ValueWrite for VarVec<T> {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        VarU64(self.0.len() as u64).write_to(stream)?;
+        stream.write_values(self.0)?;
+        Ok(())
+    }
+}
+
+impl ValueRead for VarString {
+    fn read(stream: &mut Stream) -> io::Result<Self> {
+        let len = VarU64::read(stream)?.0;
+        let data = stream.read_exact_size(len)?;
+        String::from_utf8(data)
+            .map(VarString)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("bytes to string {}", e)))
+    }
+}
+impl ValueWrite for VarString {
+    fn write_to(self, stream: &mut Stream) -> io::Result<()> {
+        VarU64(self.0.len() as u64).write_to(stream)?;
+        stream.write_all(self.0.as_bytes())
+    }
+}
+
+wrapper!(
+    [] VarU64<>(u64);
+    [] VarI64<>(i64);
+    [] SLeb64<>(i64);
+    [T] VarVec<T>(Vec<T>);
+    [] VarString<>(String);
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::Bytes;
+    use crate::stream::Stream;
+    use crate::varint::{SLeb64, VarI64, VarString, VarU64, VarVec};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_varint() {
+        let mut stream = Stream::empty();
+        stream.write_value(VarU64(624485)).unwrap();
+        stream.write_value(SLeb64(-123456)).unwrap();
+        stream.write_value(VarI64(-2)).unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![0xE5, 0x8E, 0x26, 0xC0, 0xBB, 0x78, 0x03]
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<VarU64>().unwrap(), VarU64(624485));
+        assert_eq!(stream.read_value::<SLeb64>().unwrap(), SLeb64(-123456));
+        assert_eq!(stream.read_value::<VarI64>().unwrap(), VarI64(-2));
+
+        for value in [0, 1, -1, 63, -64, 64, -65, i64::MAX, i64::MIN] {
+            let mut stream = Stream::empty();
+            stream.write_value(SLeb64(value)).unwrap();
+            stream.write_value(VarI64(value)).unwrap();
+            stream.write_value(VarU64(value as u64)).unwrap();
+            stream.seek_start().unwrap();
+            assert_eq!(*stream.read_value::<SLeb64>().unwrap(), value);
+            assert_eq!(*stream.read_value::<VarI64>().unwrap(), value);
+            assert_eq!(*stream.read_value::<VarU64>().unwrap(), value as u64);
+        }
+    }
+    #[test]
+    fn test_varint_error() {
+        let mut stream: Stream = vec![0xFF; 10].into();
+        assert_eq!(
+            stream.read_value::<VarU64>().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        let mut stream: Stream = vec![0x80, 0x80].into();
+        assert_eq!(
+            stream.read_value::<VarU64>().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+    #[test]
+    fn test_var_len_prefix() {
+        let mut stream = Stream::empty();
+        stream
+            .write_value(VarVec(vec![1_u16, 2]))
+            .unwrap()
+            .write_value(VarString("abc".to_string()))
+            .unwrap();
+        assert_eq!(
+            stream.copy_data().unwrap(),
+            vec![2, 1, 0, 2, 0, 3, b'a', b'b', b'c']
+        );
+        stream.seek_start().unwrap();
+        assert_eq!(stream.read_value::<VarVec<u16>>().unwrap().0, vec![1, 2]);
+        assert_eq!(stream.read_value::<VarString>().unwrap().as_str(), "abc");
+    }
+}
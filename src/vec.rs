@@ -1,7 +1,7 @@
 use crate::pin::Pin;
 use crate::stream::{Data, Stream};
 use std::io;
-use std::io::{Seek, SeekFrom, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 impl Stream {
     pub fn copy_data(&self) -> io::Result<Vec<u8>> {
@@ -26,25 +26,46 @@ impl Stream {
                 *self.length.borrow_mut() = 0;
                 std::mem::take(data.get_mut())
             }
+            #[cfg(feature = "mmap")]
+            Data::Mmap { data, .. } => {
+                *self.length.borrow_mut() = 0;
+                let data = data.get_ref().to_vec();
+                *self.data.get_mut() = Data::from(vec![]);
+                data
+            }
+            Data::Shared { data, .. } => {
+                *self.length.borrow_mut() = 0;
+                let data = data.get_ref().as_ref().to_vec();
+                *self.data.get_mut() = Data::from(vec![]);
+                data
+            }
+            Data::Dyn { data, .. } => {
+                /* 从开头读取全部数据后截断,不能截断的后端保持原样并返回 Unsupported */
+                let position = data.stream_position()?;
+                data.seek(SeekFrom::Start(0))?;
+                let mut copy = Vec::new();
+                data.read_to_end(&mut copy)?;
+                if let Err(e) = crate::dynamic::set_len(data.as_mut(), 0) {
+                    data.seek(SeekFrom::Start(position))?;
+                    return Err(e);
+                }
+                data.seek(SeekFrom::Start(0))?;
+                *self.length.borrow_mut() = 0;
+                copy
+            }
         })
     }
     pub fn align(&mut self, align: u64) -> io::Result<&mut Self> {
         let remainder = *self.length.borrow() % align;
         if remainder != 0 {
             let padding = align - remainder;
+            #[cfg(feature = "file")]
+            self.spill_check(self.length() + padding)?;
             self.pin()?;
             self.seek(SeekFrom::End(0))?;
-            match &mut self.data.get_mut() {
-                #[cfg(feature = "file")]
-                Data::File { data, .. } => {
-                    use std::io::Write;
-                    data.write_all(&vec![0_u8; padding as usize])?;
-                }
-                Data::Mem { data, .. } => {
-                    data.get_mut()
-                        .resize((*self.length.borrow() + padding) as usize, 0u8);
-                }
-            }
+            self.data
+                .get_mut()
+                .set_len(*self.length.borrow() + padding)?;
             self.un_pin()?;
             *self.length.borrow_mut() += padding;
         }